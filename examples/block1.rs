@@ -1,8 +1,9 @@
 use std::{
     io,
+    io::BufRead,    // <--- bring read_until() into scope
     io::IsTerminal, // <--- bring is_terminal() into scope
     io::Write,      // <--- bring flush() into scope
-    process::Command,
+    process::{Command, ExitStatus},
 };
 
 // This struct doesn't use lifetimes to keep the code simple.
@@ -22,24 +23,33 @@ impl Cmd {
         })
     }
 
-    fn run(self) {
+    fn run(self) -> Option<ExitStatus> {
         match Command::new(self.binary).args(self.args).spawn() {
-            Ok(mut child) => {
-                child.wait().expect("command wasn't running");
+            Ok(mut child) => Some(child.wait().expect("command wasn't running")),
+            Err(e) => {
+                eprintln!("{:?}", e);
+                None
             }
-            Err(e) => eprintln!("{:?}", e),
         }
     }
 }
 
 fn main() {
+    let mut status = 0;
     loop {
         show_prompt();
-        let line = read_line();
+        // End of input (Ctrl-D or a closed pipe).
+        let Some(line) = read_line() else {
+            break;
+        };
         if let Some(command) = Cmd::from_line(&line) {
-            command.run();
+            if let Some(exit_status) = command.run() {
+                status = exit_status.code().unwrap_or(1);
+            }
         }
     }
+    // Like other shells, exit with the status of the last command.
+    std::process::exit(status);
 }
 
 /// If stdout is printed to a terminal, print a prompt.
@@ -54,12 +64,20 @@ fn show_prompt() {
     }
 }
 
-fn read_line() -> String {
-    let mut line = String::new();
-    io::stdin()
-        .read_line(&mut line)
-        .expect("failed to read line from stdin");
-    line
+/// Read a line from stdin, or `None` once the input ended.
+///
+/// Bytes that aren't valid UTF-8 are replaced with the replacement character,
+/// so a binary file piped into the shell doesn't crash it.
+fn read_line() -> Option<String> {
+    let mut line = Vec::new();
+    match io::stdin().lock().read_until(b'\n', &mut line) {
+        Ok(0) => None,
+        Ok(_) => Some(String::from_utf8_lossy(&line).into_owned()),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
 }
 
 #[cfg(test)]
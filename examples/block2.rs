@@ -1,5 +1,6 @@
 use std::{
     io,
+    io::BufRead,
     io::IsTerminal,
     io::Write,
     process::{Command, Output},
@@ -87,7 +88,7 @@ struct Chain {
 }
 
 impl Chain {
-    fn run(self) {
+    fn run(self) -> Option<Output> {
         let mut prev_output: Option<Output> = None;
         for e in self.elements {
             match e {
@@ -95,14 +96,14 @@ impl Chain {
                     prev_output = cmd.run();
                 }
                 Element::And => {
-                    let status = prev_output.expect("no command before &&").status;
+                    let status = prev_output.as_ref().expect("no command before &&").status;
                     if !status.success() {
                         break;
                     }
                     prev_output = None;
                 }
                 Element::Or => {
-                    let status = prev_output.expect("no command before ||").status;
+                    let status = prev_output.as_ref().expect("no command before ||").status;
                     if status.success() {
                         break;
                     }
@@ -110,6 +111,7 @@ impl Chain {
                 }
             }
         }
+        prev_output
     }
 }
 
@@ -140,14 +142,22 @@ impl Cmd {
 }
 
 fn main() {
+    let mut status = 0;
     loop {
         show_prompt();
-        let line = read_line();
+        // End of input (Ctrl-D or a closed pipe).
+        let Some(line) = read_line() else {
+            break;
+        };
         let chains = chains_from_line(line);
         for chain in chains {
-            chain.run();
+            if let Some(output) = chain.run() {
+                status = output.status.code().unwrap_or(1);
+            }
         }
     }
+    // Like other shells, exit with the status of the last command.
+    std::process::exit(status);
 }
 
 /// If `stdout` is printed to a terminal, print a prompt.
@@ -162,12 +172,20 @@ fn show_prompt() {
     }
 }
 
-fn read_line() -> String {
-    let mut line = String::new();
-    io::stdin()
-        .read_line(&mut line)
-        .expect("failed to read line from stdin");
-    line
+/// Read a line from stdin, or `None` once the input ended.
+///
+/// Bytes that aren't valid UTF-8 are replaced with the replacement character,
+/// so a binary file piped into the shell doesn't crash it.
+fn read_line() -> Option<String> {
+    let mut line = Vec::new();
+    match io::stdin().lock().read_until(b'\n', &mut line) {
+        Ok(0) => None,
+        Ok(_) => Some(String::from_utf8_lossy(&line).into_owned()),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
 }
 
 fn chains_from_line(line: String) -> Vec<Chain> {
@@ -1,5 +1,6 @@
 use std::{
     io,
+    io::BufRead,
     io::IsTerminal,
     io::Write,
     process::{Command, Output},
@@ -164,7 +165,7 @@ struct Chain {
 }
 
 impl Chain {
-    fn run(self) {
+    fn run(self) -> Option<Output> {
         let mut prev_output: Option<Output> = None;
         for e in self.elements {
             match e {
@@ -172,14 +173,14 @@ impl Chain {
                     prev_output = cmd.run();
                 }
                 Element::And => {
-                    let status = prev_output.expect("no command before &&").status;
+                    let status = prev_output.as_ref().expect("no command before &&").status;
                     if !status.success() {
                         break;
                     }
                     prev_output = None;
                 }
                 Element::Or => {
-                    let status = prev_output.expect("no command before ||").status;
+                    let status = prev_output.as_ref().expect("no command before ||").status;
                     if status.success() {
                         break;
                     }
@@ -187,6 +188,7 @@ impl Chain {
                 }
             }
         }
+        prev_output
     }
 }
 
@@ -208,12 +210,12 @@ impl Cmd {
     fn run(self) -> Option<Output> {
         let result = match self.binary.as_ref() {
             "cd" => {
-                let dir = self.args.get(0)?;
+                let dir = self.args.first()?;
                 let dir = std::path::PathBuf::from(dir);
                 builtins::Cd::new(dir).run()
             }
             "exit" => {
-                let status = match self.args.get(0) {
+                let status = match self.args.first() {
                     Some(status) => status.parse().unwrap_or(0),
                     None => 0,
                 };
@@ -224,11 +226,10 @@ impl Cmd {
 
         // We print errors here, but we don't stop the shell.
         // This could also be done further up the call stack.
-        if let Err(e) = result {
+        result.unwrap_or_else(|e| {
             eprintln!("{}", e);
-        };
-
-        None
+            None
+        })
     }
 
     fn run_external(self) -> Result<Option<Output>> {
@@ -239,14 +240,22 @@ impl Cmd {
 }
 
 fn main() {
+    let mut status = 0;
     loop {
         show_prompt();
-        let line = read_line();
+        // End of input (Ctrl-D or a closed pipe).
+        let Some(line) = read_line() else {
+            break;
+        };
         let chains = chains_from_line(line);
         for chain in chains {
-            chain.run();
+            if let Some(output) = chain.run() {
+                status = output.status.code().unwrap_or(1);
+            }
         }
     }
+    // Like other shells, exit with the status of the last command.
+    std::process::exit(status);
 }
 
 /// If `stdout` is printed to a terminal, print a prompt.
@@ -261,12 +270,20 @@ fn show_prompt() {
     }
 }
 
-fn read_line() -> String {
-    let mut line = String::new();
-    io::stdin()
-        .read_line(&mut line)
-        .expect("failed to read line from stdin");
-    line
+/// Read a line from stdin, or `None` once the input ended.
+///
+/// Bytes that aren't valid UTF-8 are replaced with the replacement character,
+/// so a binary file piped into the shell doesn't crash it.
+fn read_line() -> Option<String> {
+    let mut line = Vec::new();
+    match io::stdin().lock().read_until(b'\n', &mut line) {
+        Ok(0) => None,
+        Ok(_) => Some(String::from_utf8_lossy(&line).into_owned()),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
 }
 
 fn chains_from_line(line: String) -> Vec<Chain> {
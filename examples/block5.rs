@@ -1,5 +1,5 @@
 use std::{
-    io::{self, IsTerminal, Write},
+    io::{self, BufRead, IsTerminal, Write},
     process::{Command, Output, Stdio},
 };
 
@@ -244,12 +244,12 @@ impl Cmd {
     fn run(&self, prev_output: Option<Output>) -> Option<Output> {
         let result = match self.binary.as_ref() {
             "cd" => {
-                let dir = self.args.get(0)?;
+                let dir = self.args.first()?;
                 let dir = std::path::PathBuf::from(dir);
                 builtins::Cd::new(dir).run()
             }
             "exit" => {
-                let status = self.args.get(0).and_then(|s| s.parse().ok()).unwrap_or(0);
+                let status = self.args.first().and_then(|s| s.parse().ok()).unwrap_or(0);
                 builtins::Exit::new(status).run()
             }
             "history" => builtins::History::new().run(),
@@ -297,18 +297,25 @@ impl Cmd {
 
 fn main() {
     let history = builtins::History::new();
+    let mut status = 0;
     loop {
         show_prompt();
-        let line = read_line();
-        history.add(&line.trim()).expect("Cannot open history file");
+        // End of input (Ctrl-D or a closed pipe).
+        let Some(line) = read_line() else {
+            break;
+        };
+        history.add(line.trim()).expect("Cannot open history file");
         let chains = chains_from_line(line);
         for chain in chains {
             let output = chain.run();
             if let Some(output) = output {
                 std::io::stdout().write_all(&output.stdout).unwrap();
+                status = output.status.code().unwrap_or(1);
             }
         }
     }
+    // Like other shells, exit with the status of the last command.
+    std::process::exit(status);
 }
 
 /// If `stdout` is printed to a terminal, print a prompt.
@@ -323,12 +330,20 @@ fn show_prompt() {
     }
 }
 
-fn read_line() -> String {
-    let mut line = String::new();
-    io::stdin()
-        .read_line(&mut line)
-        .expect("failed to read line from stdin");
-    line
+/// Read a line from stdin, or `None` once the input ended.
+///
+/// Bytes that aren't valid UTF-8 are replaced with the replacement character,
+/// so a binary file piped into the shell doesn't crash it.
+fn read_line() -> Option<String> {
+    let mut line = Vec::new();
+    match io::stdin().lock().read_until(b'\n', &mut line) {
+        Ok(0) => None,
+        Ok(_) => Some(String::from_utf8_lossy(&line).into_owned()),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
 }
 
 fn chains_from_line(line: String) -> Vec<Chain> {
@@ -0,0 +1,1524 @@
+//! This module contains the built-in commands of the shell.
+//!
+//! Builtins run inside the shell process, so they can't just print to the
+//! real stdout: their output has to follow redirections and pipes like
+//! `history > hist.txt` or `history | grep git`. That's why they write to
+//! the [`Io`] handles they are given instead.
+
+use crate::exec::{is_builtin, spawn_error, status_code};
+use crate::hash::{self, CommandHash};
+use crate::jobs::JobTable;
+use crate::options::Options;
+use crate::redirect::error_message;
+use crate::traps::{Event, Traps};
+use crate::{signals, sys, Result, Shell};
+use std::io::{self, Write};
+use std::os::fd::RawFd;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Component, Path, PathBuf};
+use std::process;
+
+/// A handle to one of the file descriptors of the shell, like 1 for stdout.
+///
+/// Writes are not buffered, so nothing is left behind when the shell
+/// restores its file descriptors after a builtin.
+pub struct FdWriter(RawFd);
+
+impl Write for FdWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        sys::write(self.0, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The output streams of a builtin.
+///
+/// They write to the file descriptors 1 and 2 of the shell, which are
+/// redirected while the builtin runs.
+pub struct Io {
+    pub stdout: FdWriter,
+    pub stderr: FdWriter,
+}
+
+impl Io {
+    pub fn new() -> Self {
+        Self {
+            stdout: FdWriter(1),
+            stderr: FdWriter(2),
+        }
+    }
+}
+
+/// The `cd` command changes the current directory.
+///
+/// Without an argument, `cd` goes to `$HOME`, and `cd -` goes back to
+/// `$OLDPWD` and prints it. A relative directory is searched in the
+/// directories of `$CDPATH` first, and the new directory is printed when it
+/// was found there. `PWD` and `OLDPWD` are updated after each change.
+///
+/// By default (`-L`), `cd` follows the logical path: `..` removes the last
+/// component of `$PWD`, so `cd ..` after `cd link` goes back to where the
+/// symbolic link is. With `-P`, symbolic links are resolved, and `$PWD` is
+/// the physical directory.
+pub struct Cd {
+    args: Vec<String>,
+}
+
+impl Cd {
+    /// Create a new `Cd` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `cd` command.
+    pub fn run(self, io: &mut Io) -> Result<i32> {
+        let mut physical = false;
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            if flags == "--" {
+                break;
+            }
+            for flag in flags[1..].chars() {
+                match flag {
+                    'L' => physical = false,
+                    'P' => physical = true,
+                    _ => return Err(format!("cd: -{flag}: invalid option").into()),
+                }
+            }
+        }
+        let dir = args.next();
+        if args.next().is_some() {
+            return Err("cd: too many arguments".into());
+        }
+
+        let (dir, mut print) = match dir.map(String::as_str) {
+            None => (non_empty_var("HOME").ok_or("cd: HOME not set")?, false),
+            Some("-") => (non_empty_var("OLDPWD").ok_or("cd: OLDPWD not set")?, true),
+            Some(dir) => (dir.to_string(), false),
+        };
+        let mut target = PathBuf::from(&dir);
+        if let Some(found) = search_cdpath(&dir) {
+            target = found;
+            print = true;
+        }
+
+        let old_pwd = current_dir();
+        let target = match target.is_absolute() {
+            true => target,
+            false => old_pwd.join(target),
+        };
+        let target = match physical {
+            true => target,
+            false => normalize(&target),
+        };
+        // `std::env::set_current_dir` changes the current directory of the process
+        // (our shell in this case).
+        std::env::set_current_dir(&target)
+            .map_err(|e| format!("cd: {dir}: {}", error_message(&e)))?;
+        let pwd = match physical {
+            true => std::env::current_dir()?,
+            false => target,
+        };
+
+        std::env::set_var("OLDPWD", old_pwd);
+        std::env::set_var("PWD", &pwd);
+        if print {
+            writeln!(io.stdout, "{}", pwd.display())?;
+        }
+        Ok(0)
+    }
+}
+
+/// The current directory of the shell, as the user reached it.
+///
+/// This is `$PWD`, which keeps the symbolic links `cd` went through, as long
+/// as it is still the directory the shell is in. Otherwise, it is the
+/// physical directory.
+pub fn current_dir() -> PathBuf {
+    let physical = std::env::current_dir().unwrap_or_default();
+    let logical = std::env::var_os("PWD").map(PathBuf::from);
+    match logical {
+        Some(logical) if logical.is_absolute() && same_file(&logical, &physical) => logical,
+        _ => physical,
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.metadata(), b.metadata()) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Find `dir` in the directories of `$CDPATH`. An empty entry is the current
+/// directory, which doesn't count as found, so `cd` doesn't print it.
+///
+/// Like in other shells, absolute paths and paths that start with `.` or
+/// `..` are never searched.
+fn search_cdpath(dir: &str) -> Option<PathBuf> {
+    if dir.starts_with('/')
+        || matches!(
+            Path::new(dir).components().next(),
+            Some(Component::CurDir | Component::ParentDir)
+        )
+    {
+        return None;
+    }
+    let cdpath = std::env::var_os("CDPATH")?;
+    for base in std::env::split_paths(&cdpath) {
+        if base.as_os_str().is_empty() {
+            if Path::new(dir).is_dir() {
+                return None;
+            }
+            continue;
+        }
+        let path = base.join(dir);
+        if path.is_dir() {
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// Remove the `.` and `..` components of `path` without looking at the
+/// file system, so `..` goes back over a symbolic link.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+/// The `exit` command exits the shell.
+///
+/// The `exit` command exits the shell with the given status code.
+/// If no status code is given, it exits with the status of the last command.
+/// The shell only exits once the current command is done, so the `EXIT`
+/// trap can still run.
+///
+/// Like in other shells, a status that isn't a number is an error, but the
+/// shell still exits, with status 2.
+pub struct Exit {
+    args: Vec<String>,
+    /// The status of the last command, used when no status code is given.
+    last_status: i32,
+}
+
+impl Exit {
+    /// Create a new `Exit` command.
+    pub fn new(args: &[String], last_status: i32) -> Self {
+        Self {
+            args: args.to_vec(),
+            last_status,
+        }
+    }
+
+    /// Run the `exit` command, which sets the status the shell exits with.
+    pub fn run(self, exiting: &mut Option<i32>, io: &mut Io) -> Result<i32> {
+        if self.args.len() > 1 {
+            return Err("exit: too many arguments".into());
+        }
+        let status = match self.args.first() {
+            None => self.last_status,
+            Some(status) => match status.parse() {
+                Ok(status) => status,
+                Err(_) => {
+                    writeln!(io.stderr, "rush: exit: {status}: numeric argument required")?;
+                    2
+                }
+            },
+        };
+        *exiting = Some(status);
+        Ok(status)
+    }
+}
+
+/// The `exec` command replaces the shell with another program.
+///
+/// `exec cmd args` runs `cmd` in the process of the shell, so the shell is
+/// gone. `-a name` passes `name` to the program as its `argv[0]`, `-l` puts
+/// a `-` in front of it like for a login shell, and `-c` starts the program
+/// with an empty environment. Without a command, the redirections of `exec`
+/// change the file descriptors of the shell for good, like in `exec 2>log`.
+pub struct Exec {
+    args: Vec<String>,
+}
+
+impl Exec {
+    /// Create a new `Exec` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `exec` command. It only returns if there is no command,
+    /// or if the program couldn't be started.
+    pub fn run(self, shell: &mut Shell, io: &mut Io) -> Result<i32> {
+        let (mut name, mut login, mut clear) = (None, false, false);
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            if flags == "--" {
+                break;
+            }
+            for flag in flags[1..].chars() {
+                match flag {
+                    'a' => name = Some(args.next().ok_or("exec: -a: option requires an argument")?),
+                    'c' => clear = true,
+                    'l' => login = true,
+                    _ => return Err(format!("exec: -{flag}: invalid option").into()),
+                }
+            }
+        }
+        let Some(binary) = args.next() else {
+            return Ok(0);
+        };
+
+        // Like other commands, the program is looked up in the table of the shell.
+        let program = match binary.contains('/') {
+            true => Some(PathBuf::from(binary)),
+            false => shell.hash.resolve(binary),
+        };
+        let mut command = process::Command::new(program.as_deref().unwrap_or(binary.as_ref()));
+        command.args(args);
+        let name = name.unwrap_or(binary);
+        match login {
+            true => command.arg0(format!("-{name}")),
+            false => command.arg0(name),
+        };
+        if clear {
+            command.env_clear();
+        }
+        io::stdout().flush()?;
+        signals::reset_actions();
+        let e = command.exec();
+        signals::reapply_actions();
+
+        if e.kind() == io::ErrorKind::NotFound {
+            shell.hash.remove(binary);
+        }
+        let (message, code) = spawn_error(binary, &e);
+        writeln!(io.stderr, "rush: exec: {binary}: {message}")?;
+        // Like in other shells, a script doesn't go on without the program
+        // it meant to replace itself with.
+        if !shell.jobs.interactive() {
+            shell.exiting = Some(code);
+        }
+        Ok(code)
+    }
+}
+
+/// The name of the history file in the user's home directory.
+const DEFAULT_HISTORY_FILE: &str = ".rush_history";
+
+/// The `history` command displays the command history.
+pub struct History {
+    history_path: PathBuf,
+}
+
+impl History {
+    /// Create a new `History` command.
+    pub fn new() -> Self {
+        // The path can be overridden by setting the `HISTORY_PATH` environment variable.
+        // Without a home directory, the history goes to the temporary directory
+        // rather than wherever the shell was started.
+        let history_path = match std::env::var_os("HISTORY_PATH") {
+            Some(path) => PathBuf::from(path),
+            None => std::env::var_os("HOME")
+                .map_or_else(std::env::temp_dir, PathBuf::from)
+                .join(DEFAULT_HISTORY_FILE),
+        };
+
+        Self { history_path }
+    }
+
+    /// Add a command to the history.
+    pub fn add(&self, command: &str) -> Result<()> {
+        let mut history = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.history_path)?;
+        writeln!(history, "{command}")?;
+        Ok(())
+    }
+
+    /// Get the commands in the history, oldest first.
+    ///
+    /// A missing history file is treated as an empty history.
+    pub fn entries(&self) -> Result<Vec<String>> {
+        match std::fs::read_to_string(&self.history_path) {
+            Ok(history) => Ok(history.lines().map(String::from).collect()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Print all the commands in the history.
+    pub fn run(self, io: &mut Io) -> Result<i32> {
+        let history = std::fs::read_to_string(&self.history_path).map_err(|e| {
+            format!(
+                "history: {}: {}",
+                self.history_path.display(),
+                error_message(&e)
+            )
+        })?;
+        io.stdout.write_all(history.as_bytes())?;
+        Ok(0)
+    }
+}
+
+/// The `set` command changes the shell options.
+///
+/// `set -o name` turns an option on and `set +o name` turns it off.
+/// Options with a single-letter flag can also be written as `set -e` or `set +e`,
+/// and flags can be combined, like in `set -euo pipefail`.
+/// `set -m` (`monitor`) turns job control on, which scripts don't have by default.
+/// Without an option name, `set -o` lists the options and their state,
+/// and `set +o` prints the `set` commands that recreate the current state.
+pub struct Set {
+    args: Vec<String>,
+}
+
+impl Set {
+    /// Create a new `Set` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `set` command.
+    pub fn run(self, options: &mut Options, jobs: &mut JobTable, io: &mut Io) -> Result<i32> {
+        let mut args = self.args.iter().peekable();
+        while let Some(arg) = args.next() {
+            let value = match arg.chars().next() {
+                Some('-') => true,
+                Some('+') => false,
+                _ => return Err(format!("set: {arg}: invalid option").into()),
+            };
+            for flag in arg[1..].chars() {
+                if flag == 'o' {
+                    match args.next_if(|name| !name.starts_with(['-', '+'])) {
+                        // Job control belongs to the job table, not to the options.
+                        Some(name) if name == "monitor" => jobs.set_monitor(value),
+                        Some(name) if Options::NAMES.contains(&name.as_str()) => {
+                            options.set(name, value);
+                        }
+                        Some(name) => {
+                            return Err(format!("set: {name}: invalid option name").into())
+                        }
+                        None => Self::list(options, jobs.monitor(), value, &mut io.stdout)?,
+                    }
+                    continue;
+                }
+                match Options::name_of_flag(flag) {
+                    _ if flag == 'm' => jobs.set_monitor(value),
+                    Some(name) => {
+                        options.set(name, value);
+                    }
+                    None => return Err(format!("set: {}{flag}: invalid option", &arg[..1]).into()),
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    /// Write the state of all options, in the format of `set -o` or `set +o`.
+    fn list(options: &Options, monitor: bool, human: bool, out: &mut impl Write) -> Result<()> {
+        let mut names = Options::NAMES.to_vec();
+        names.push("monitor");
+        names.sort();
+        for name in names {
+            let on = match name {
+                "monitor" => monitor,
+                name => options.get(name).unwrap_or_default(),
+            };
+            if human {
+                writeln!(out, "{name:<15}\t{}", if on { "on" } else { "off" })?;
+            } else {
+                writeln!(out, "set {}o {name}", if on { '-' } else { '+' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `shopt` command changes the extended options, like `nullglob`.
+///
+/// `shopt -s name` turns an option on and `shopt -u name` turns it off.
+/// Without `-s` or `-u`, it lists the options, and its status tells whether
+/// they are all on (`-q` makes it quiet). `-p` lists them as `shopt` commands,
+/// and `-o` works on the options of `set -o` instead.
+pub struct Shopt {
+    args: Vec<String>,
+}
+
+impl Shopt {
+    /// Create a new `Shopt` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `shopt` command.
+    pub fn run(self, options: &mut Options, io: &mut Io) -> Result<i32> {
+        let mut value = None;
+        let (mut print, mut quiet, mut set_options) = (false, false, false);
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags[1..].chars() {
+                match flag {
+                    's' => value = Some(true),
+                    'u' => value = Some(false),
+                    'p' => print = true,
+                    'q' => quiet = true,
+                    'o' => set_options = true,
+                    _ => return Err(format!("shopt: -{flag}: invalid option").into()),
+                }
+            }
+        }
+        let known = match set_options {
+            true => Options::NAMES,
+            false => Options::SHOPT_NAMES,
+        };
+
+        let mut status = 0;
+        let mut names = Vec::new();
+        for name in args {
+            match known.iter().find(|known| *known == name) {
+                Some(name) => names.push(*name),
+                None => {
+                    writeln!(io.stderr, "rush: shopt: {name}: invalid shell option name")?;
+                    status = 1;
+                }
+            }
+        }
+
+        match value {
+            Some(value) if !names.is_empty() || status != 0 => {
+                for name in names {
+                    options.set(name, value);
+                }
+            }
+            // `shopt -s` lists the options that are on, and `shopt -u` the others.
+            Some(value) => {
+                let names = known.iter().copied();
+                let names = names.filter(|name| options.get(name) == Some(value));
+                Self::list(options, names, print, set_options, &mut io.stdout)?;
+            }
+            None => {
+                if names.is_empty() && status == 0 {
+                    names = known.to_vec();
+                }
+                if names.iter().any(|name| options.get(name) != Some(true)) {
+                    status = 1;
+                }
+                if !quiet {
+                    Self::list(options, names, print, set_options, &mut io.stdout)?;
+                }
+            }
+        }
+        Ok(status)
+    }
+
+    /// Write the state of the options called `names`, in the format of `shopt`
+    /// or `shopt -p`.
+    fn list<'a>(
+        options: &Options,
+        names: impl IntoIterator<Item = &'a str>,
+        print: bool,
+        set_options: bool,
+        out: &mut impl Write,
+    ) -> Result<()> {
+        for name in names {
+            let on = options.get(name).unwrap_or_default();
+            match (print, set_options) {
+                (false, _) => writeln!(out, "{name:<15}\t{}", if on { "on" } else { "off" })?,
+                (true, false) => writeln!(out, "shopt {} {name}", if on { "-s" } else { "-u" })?,
+                (true, true) => writeln!(out, "set {}o {name}", if on { '-' } else { '+' })?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `jobs` command lists the background jobs, or the ones given as job specs.
+///
+/// Jobs that are done are listed one last time, and then forgotten.
+pub struct Jobs {
+    specs: Vec<String>,
+}
+
+impl Jobs {
+    /// Create a new `Jobs` command.
+    pub fn new(specs: &[String]) -> Self {
+        Self {
+            specs: specs.to_vec(),
+        }
+    }
+
+    /// Run the `jobs` command.
+    pub fn run(self, jobs: &mut JobTable, io: &mut Io) -> Result<i32> {
+        let ids = if self.specs.is_empty() {
+            jobs.ids()
+        } else {
+            self.specs
+                .iter()
+                .map(|spec| jobs.find(Some(spec)))
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|e| format!("jobs: {e}"))?
+        };
+        jobs.list(&ids, &mut io.stdout)?;
+        Ok(0)
+    }
+}
+
+/// The `fg` command moves a job to the foreground, continues it if it is
+/// stopped, and waits for it.
+///
+/// Without a job spec, it uses the current job, `%+`.
+pub struct Fg {
+    spec: Option<String>,
+}
+
+impl Fg {
+    /// Create a new `Fg` command.
+    pub fn new(spec: Option<String>) -> Self {
+        Self { spec }
+    }
+
+    /// Run the `fg` command. Its status is the status of the job.
+    pub fn run(self, jobs: &mut JobTable, io: &mut Io) -> Result<i32> {
+        let error = |e| format!("fg: {e}");
+        let id = jobs.find(self.spec.as_deref()).map_err(error)?;
+        let status = jobs.foreground(id, &mut io.stdout).map_err(error)?;
+        Ok(status_code(status))
+    }
+}
+
+/// The `bg` command lets a stopped job continue in the background.
+///
+/// Without a job spec, it uses the current job, `%+`.
+pub struct Bg {
+    spec: Option<String>,
+}
+
+impl Bg {
+    /// Create a new `Bg` command.
+    pub fn new(spec: Option<String>) -> Self {
+        Self { spec }
+    }
+
+    /// Run the `bg` command.
+    pub fn run(self, jobs: &mut JobTable, io: &mut Io) -> Result<i32> {
+        let error = |e| format!("bg: {e}");
+        let id = jobs.find(self.spec.as_deref()).map_err(error)?;
+        jobs.background(id, &mut io.stdout).map_err(error)?;
+        Ok(0)
+    }
+}
+
+/// The `disown` command makes the shell forget a job, so it is not reported
+/// on and doesn't get `SIGHUP` when the shell exits. The job keeps running.
+///
+/// `-h` keeps the job in the table and only spares it the `SIGHUP`, `-a`
+/// works on all jobs and `-r` on the running ones. Without a job spec or a
+/// pid, it is the current job.
+pub struct Disown {
+    args: Vec<String>,
+}
+
+impl Disown {
+    /// Create a new `Disown` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `disown` command.
+    pub fn run(self, jobs: &mut JobTable, io: &mut Io) -> Result<i32> {
+        let (mut keep, mut all, mut running) = (false, false, false);
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags[1..].chars() {
+                match flag {
+                    'h' => keep = true,
+                    'a' => all = true,
+                    'r' => running = true,
+                    _ => return Err(format!("disown: -{flag}: invalid option").into()),
+                }
+            }
+        }
+
+        let mut status = 0;
+        let mut ids = Vec::new();
+        let specs: Vec<_> = args.collect();
+        if specs.is_empty() && (all || running) {
+            ids = match running {
+                true => jobs.running_ids(),
+                false => jobs.ids(),
+            };
+        } else if specs.is_empty() {
+            ids.push(jobs.find(None).map_err(|e| format!("disown: {e}"))?);
+        }
+        for spec in specs {
+            let id = match spec.parse() {
+                Ok(pid) => jobs
+                    .find_pid(pid)
+                    .ok_or_else(|| format!("{pid}: no such job")),
+                Err(_) => jobs.find(Some(spec)),
+            };
+            match id {
+                Ok(id) => ids.push(id),
+                Err(e) => {
+                    writeln!(io.stderr, "rush: disown: {e}")?;
+                    status = 1;
+                }
+            }
+        }
+        for id in ids {
+            jobs.disown(id, keep).map_err(|e| format!("disown: {e}"))?;
+        }
+        Ok(status)
+    }
+}
+
+/// The `wait` command waits for background jobs to end, given as job specs
+/// like `%1` or pids like `$!`, or for all of them. Its status is the status
+/// of the last one.
+///
+/// `wait -n` waits for the first job that ends instead. A signal with a trap
+/// stops the wait, with a status of 128 plus the signal number, like in bash.
+pub struct Wait {
+    args: Vec<String>,
+}
+
+impl Wait {
+    /// Create a new `Wait` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `wait` command.
+    pub fn run(self, jobs: &mut JobTable, io: &mut Io) -> Result<i32> {
+        let mut any = false;
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags[1..].chars() {
+                match flag {
+                    'n' => any = true,
+                    _ => return Err(format!("wait: -{flag}: invalid option").into()),
+                }
+            }
+        }
+
+        let mut status = 0;
+        let mut ids = Vec::new();
+        let mut all = true;
+        for arg in args {
+            all = false;
+            let id = match arg.parse() {
+                Ok(pid) => jobs
+                    .find_pid(pid)
+                    .ok_or_else(|| format!("pid {pid} is not a child of this shell")),
+                Err(_) if arg.starts_with('%') => jobs.find(Some(arg)),
+                Err(_) => return Err(format!("wait: `{arg}': not a pid or valid job spec").into()),
+            };
+            match id {
+                Ok(id) => ids.push(id),
+                Err(e) => {
+                    writeln!(io.stderr, "rush: wait: {e}")?;
+                    status = 127;
+                }
+            }
+        }
+        if all {
+            ids = jobs.ids();
+            if ids.is_empty() {
+                return Ok(if any { 127 } else { 0 });
+            }
+        } else if ids.is_empty() {
+            return Ok(status);
+        }
+
+        match jobs.wait(&ids, any) {
+            // Like in bash, waiting for all jobs succeeds whatever they did.
+            Ok(_) if all && !any => Ok(0),
+            Ok(Some(job_status)) => Ok(status_code(job_status)),
+            Ok(None) => Ok(127),
+            Err(signal) => {
+                // Ctrl-C leaves the cursor after `^C`.
+                if signal == libc::SIGINT && jobs.interactive() {
+                    writeln!(io.stderr)?;
+                }
+                Ok(128 + signal)
+            }
+        }
+    }
+}
+
+/// The `trap` command runs a command when a signal arrives.
+///
+/// `trap 'cmd' INT TERM` sets a trap, `trap '' INT` ignores the signal,
+/// and `trap - INT` restores its default action. Without arguments or
+/// with `-p`, it prints the traps.
+pub struct Trap {
+    args: Vec<String>,
+}
+
+impl Trap {
+    /// Create a new `Trap` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `trap` command.
+    pub fn run(self, traps: &mut Traps, io: &mut Io) -> Result<i32> {
+        let args = match self.args.first().map(String::as_str) {
+            Some("--") => &self.args[1..],
+            _ => &self.args[..],
+        };
+        let (command, specs) = match args {
+            [] => {
+                traps.list(&[], &mut io.stdout)?;
+                return Ok(0);
+            }
+            [flag, specs @ ..] if flag == "-p" => {
+                let events = specs
+                    .iter()
+                    .map(|spec| parse(spec))
+                    .collect::<Result<Vec<_>>>()?;
+                traps.list(&events, &mut io.stdout)?;
+                return Ok(0);
+            }
+            // `trap INT` is short for `trap - INT`.
+            [spec] if Event::parse(spec).is_some() => (None, std::slice::from_ref(spec)),
+            [command, specs @ ..] if command == "-" => (None, specs),
+            [command, specs @ ..] => (Some(command), specs),
+        };
+
+        let mut status = 0;
+        for spec in specs {
+            match parse(spec) {
+                Ok(event) => traps.set(event, command.cloned()),
+                Err(e) => {
+                    writeln!(io.stderr, "rush: {e}")?;
+                    status = 1;
+                }
+            }
+        }
+        Ok(status)
+    }
+}
+
+fn parse(spec: &str) -> Result<Event> {
+    Event::parse(spec).ok_or_else(|| format!("trap: {spec}: invalid signal specification").into())
+}
+
+/// The `kill` command sends a signal to processes, given as pids, as
+/// process groups like `-1234`, or as job specs like `%1`.
+///
+/// The signal is `TERM` unless `-s NAME`, `-n NUMBER` or `-NAME` says
+/// otherwise. `kill -l` lists the signals, and `kill -l 130` names the
+/// signal that killed a command with that status.
+pub struct Kill {
+    args: Vec<String>,
+}
+
+impl Kill {
+    /// Create a new `Kill` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `kill` command.
+    pub fn run(self, jobs: &mut JobTable, io: &mut Io) -> Result<i32> {
+        let mut signal = libc::SIGTERM;
+        let mut args = &self.args[..];
+        match args {
+            [flag, rest @ ..] if flag == "-l" || flag == "-L" => return Self::list(rest, io),
+            [flag, name, rest @ ..] if flag == "-s" || flag == "-n" => {
+                signal = signal_number(name)?;
+                args = rest;
+            }
+            [flag] if flag == "-s" || flag == "-n" => {
+                return Err(format!("kill: {flag}: option requires an argument").into());
+            }
+            [flag, rest @ ..] if flag != "--" && flag.len() > 1 && flag.starts_with('-') => {
+                signal = signal_number(&flag[1..])?;
+                args = rest;
+            }
+            _ => {}
+        }
+        if let [flag, rest @ ..] = args {
+            if flag == "--" {
+                args = rest;
+            }
+        }
+        if args.is_empty() {
+            return Err(
+                "kill: usage: kill [-s sigspec | -n signum | -sigspec] pid | jobspec ... \
+                 or kill -l [sigspec]"
+                    .into(),
+            );
+        }
+
+        let mut status = 0;
+        for target in args {
+            let sent = if target.starts_with('%') {
+                jobs.find(Some(target))
+                    .and_then(|id| jobs.signal(id, signal))
+            } else if let Ok(pid) = target.parse() {
+                sys::kill(pid, signal).map_err(|e| format!("({pid}) - {}", error_message(&e)))
+            } else {
+                Err(format!("{target}: arguments must be process or job IDs"))
+            };
+            if let Err(e) = sent {
+                writeln!(io.stderr, "rush: kill: {e}")?;
+                status = 1;
+            }
+        }
+        Ok(status)
+    }
+
+    /// List the signals like `kill -l`, or translate the given ones between
+    /// names and numbers. A status above 128 stands for the signal that
+    /// killed a command.
+    fn list(args: &[String], io: &mut Io) -> Result<i32> {
+        if args.is_empty() {
+            let mut signals = signals::NAMES.to_vec();
+            signals.sort_by_key(|(_, number)| *number);
+            for (i, (name, number)) in signals.iter().enumerate() {
+                let end = if i % 5 == 4 || i + 1 == signals.len() {
+                    "\n"
+                } else {
+                    "\t"
+                };
+                write!(io.stdout, "{number:2}) SIG{name}{end}")?;
+            }
+            return Ok(0);
+        }
+        let mut status = 0;
+        for arg in args {
+            let listed = match arg.parse::<libc::c_int>() {
+                Ok(number) => {
+                    let number = if number > 128 { number - 128 } else { number };
+                    signals::name(number).map(str::to_string)
+                }
+                Err(_) => signals::number(arg).map(|number| number.to_string()),
+            };
+            match listed {
+                Some(listed) => writeln!(io.stdout, "{listed}")?,
+                None => {
+                    writeln!(io.stderr, "rush: kill: {arg}: invalid signal specification")?;
+                    status = 1;
+                }
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// Find the signal called `name` for `kill`, where `0` checks that the
+/// processes exist without sending anything.
+fn signal_number(name: &str) -> Result<libc::c_int> {
+    match name {
+        "0" => Ok(0),
+        name => signals::number(name)
+            .ok_or_else(|| format!("kill: {name}: invalid signal specification").into()),
+    }
+}
+
+/// The `hash` command shows and changes the table of programs the shell
+/// found in `$PATH`.
+///
+/// `hash name` looks a program up and remembers it, `hash -p path name`
+/// tells the shell where it is, and `hash -r` forgets all programs.
+/// Without names, it lists the table.
+pub struct Hash {
+    args: Vec<String>,
+}
+
+impl Hash {
+    /// Create a new `Hash` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `hash` command.
+    pub fn run(self, hash: &mut CommandHash, io: &mut Io) -> Result<i32> {
+        let mut path = None;
+        let mut cleared = false;
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags[1..].chars() {
+                match flag {
+                    'r' => {
+                        hash.clear();
+                        cleared = true;
+                    }
+                    'p' => path = Some(args.next().ok_or("hash: -p: option requires an argument")?),
+                    _ => return Err(format!("hash: -{flag}: invalid option").into()),
+                }
+            }
+        }
+        let names: Vec<_> = args.collect();
+
+        if names.is_empty() {
+            if !cleared {
+                match hash.is_empty() {
+                    true => writeln!(io.stdout, "hash: hash table empty")?,
+                    false => hash.list(&mut io.stdout)?,
+                }
+            }
+            return Ok(0);
+        }
+        let mut status = 0;
+        for name in names {
+            match path {
+                Some(path) => hash.insert(name, PathBuf::from(path)),
+                // Names with a `/` are not looked up in `$PATH`.
+                None if name.contains('/') => {}
+                None => {
+                    if hash.add(name).is_none() {
+                        writeln!(io.stderr, "rush: hash: {name}: not found")?;
+                        status = 1;
+                    }
+                }
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// What a command name stands for.
+enum Found {
+    /// A word that changes how the command after it runs, like `time`.
+    Keyword,
+    Builtin,
+    /// A program the shell remembers, see `hash`.
+    Hashed(PathBuf),
+    File(PathBuf),
+}
+
+impl Found {
+    /// Find what `name` stands for, in the order the shell would try.
+    /// With `all`, every program called `name` in `$PATH` is listed, not
+    /// just the one that would run.
+    fn find(name: &str, hash: &CommandHash, all: bool) -> Vec<Found> {
+        let mut found = Vec::new();
+        if name == "time" || name == "coproc" {
+            found.push(Found::Keyword);
+        }
+        if is_builtin(name) {
+            found.push(Found::Builtin);
+        }
+        if name.contains('/') {
+            if hash::is_executable(name.as_ref()) {
+                found.push(Found::File(PathBuf::from(name)));
+            }
+        } else if all {
+            found.extend(hash::search_all(name).into_iter().map(Found::File));
+        } else if found.is_empty() {
+            found.extend(match hash.get(name) {
+                Some(path) => Some(Found::Hashed(path.to_path_buf())),
+                None => hash::search(name).map(Found::File),
+            });
+        }
+        found
+    }
+
+    /// The path of the program, if it is one.
+    fn path(&self) -> Option<&PathBuf> {
+        match self {
+            Found::Keyword | Found::Builtin => None,
+            Found::Hashed(path) | Found::File(path) => Some(path),
+        }
+    }
+
+    /// Describe what `name` is, like `ls is /usr/bin/ls`.
+    fn describe(&self, name: &str) -> String {
+        match self {
+            Found::Keyword => format!("{name} is a shell keyword"),
+            Found::Builtin => format!("{name} is a shell builtin"),
+            Found::Hashed(path) => format!("{name} is hashed ({})", path.display()),
+            Found::File(path) => format!("{name} is {}", path.display()),
+        }
+    }
+}
+
+/// The `type` command tells what each name would run: a builtin or a program.
+///
+/// There are no aliases or functions in this shell, so those are never found.
+pub struct Type {
+    args: Vec<String>,
+}
+
+impl Type {
+    /// Create a new `Type` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `type` command.
+    ///
+    /// `-t` only prints the kind of each name, `-p` the path of programs,
+    /// and `-a` lists everything a name could stand for.
+    pub fn run(self, hash: &mut CommandHash, io: &mut Io) -> Result<i32> {
+        let (mut all, mut kind, mut path) = (false, false, false);
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags[1..].chars() {
+                match flag {
+                    'a' => all = true,
+                    't' => kind = true,
+                    'p' => path = true,
+                    _ => return Err(format!("type: -{flag}: invalid option").into()),
+                }
+            }
+        }
+
+        let mut status = 0;
+        for name in args {
+            let found = Found::find(name, hash, all);
+            if found.is_empty() {
+                if !kind && !path {
+                    writeln!(io.stderr, "rush: type: {name}: not found")?;
+                }
+                status = 1;
+            }
+            for found in &found {
+                if kind {
+                    let kind = match found {
+                        Found::Keyword => "keyword",
+                        Found::Builtin => "builtin",
+                        Found::Hashed(_) | Found::File(_) => "file",
+                    };
+                    writeln!(io.stdout, "{kind}")?;
+                } else if path {
+                    if let Some(path) = found.path() {
+                        writeln!(io.stdout, "{}", path.display())?;
+                    }
+                } else {
+                    writeln!(io.stdout, "{}", found.describe(name))?;
+                }
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// The `command` command runs a command without looking for functions or
+/// aliases. With `-v` or `-V`, it tells what the command would run instead.
+///
+/// The shell runs `command name args` as `name args` itself, so this only
+/// handles the options.
+pub struct Command {
+    args: Vec<String>,
+}
+
+impl Command {
+    /// Create a new `Command` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `command` command.
+    ///
+    /// `-v` prints the name of a builtin or the path of a program, and `-V`
+    /// describes them like `type` does.
+    pub fn run(self, hash: &mut CommandHash, io: &mut Io) -> Result<i32> {
+        let mut verbose = None;
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags[1..].chars() {
+                match flag {
+                    'v' => verbose = Some(false),
+                    'V' => verbose = Some(true),
+                    _ => return Err(format!("command: -{flag}: invalid option").into()),
+                }
+            }
+        }
+        let Some(verbose) = verbose else {
+            // Without a command, there is nothing to run.
+            return match args.next() {
+                Some(_) => Err("command: -v or -V is needed before the command".into()),
+                None => Ok(0),
+            };
+        };
+
+        let mut status = 0;
+        for name in args {
+            match Found::find(name, hash, false).first() {
+                Some(found) if verbose => writeln!(io.stdout, "{}", found.describe(name))?,
+                Some(found) => match found.path() {
+                    Some(path) => writeln!(io.stdout, "{}", path.display())?,
+                    None => writeln!(io.stdout, "{name}")?,
+                },
+                None => {
+                    if verbose {
+                        writeln!(io.stderr, "rush: command: {name}: not found")?;
+                    }
+                    status = 1;
+                }
+            }
+        }
+        Ok(status)
+    }
+}
+
+/// A resource `ulimit` can limit.
+struct Limit {
+    /// The option of `ulimit` for the resource, like `n` for `-n`.
+    flag: char,
+    resource: libc::c_int,
+    description: &'static str,
+    /// The unit users give the limit in, if it isn't a plain count.
+    unit: Option<&'static str>,
+    /// The size of the unit, in the unit of `setrlimit`.
+    factor: libc::rlim_t,
+}
+
+/// The resources `ulimit` knows, in the order `ulimit -a` lists them.
+const LIMITS: &[Limit] = &[
+    Limit {
+        flag: 'c',
+        resource: libc::RLIMIT_CORE as libc::c_int,
+        description: "core file size",
+        unit: Some("blocks"),
+        factor: 512,
+    },
+    Limit {
+        flag: 'f',
+        resource: libc::RLIMIT_FSIZE as libc::c_int,
+        description: "file size",
+        unit: Some("blocks"),
+        factor: 512,
+    },
+    Limit {
+        flag: 'n',
+        resource: libc::RLIMIT_NOFILE as libc::c_int,
+        description: "open files",
+        unit: None,
+        factor: 1,
+    },
+    Limit {
+        flag: 's',
+        resource: libc::RLIMIT_STACK as libc::c_int,
+        description: "stack size",
+        unit: Some("kbytes"),
+        factor: 1024,
+    },
+    Limit {
+        flag: 't',
+        resource: libc::RLIMIT_CPU as libc::c_int,
+        description: "cpu time",
+        unit: Some("seconds"),
+        factor: 1,
+    },
+    Limit {
+        flag: 'u',
+        resource: libc::RLIMIT_NPROC as libc::c_int,
+        description: "max user processes",
+        unit: None,
+        factor: 1,
+    },
+    Limit {
+        flag: 'v',
+        resource: libc::RLIMIT_AS as libc::c_int,
+        description: "virtual memory",
+        unit: Some("kbytes"),
+        factor: 1024,
+    },
+];
+
+impl Limit {
+    /// Format `value` in the unit of the limit, like `ulimit` shows it.
+    fn show(&self, value: libc::rlim_t) -> String {
+        match value {
+            libc::RLIM_INFINITY => "unlimited".to_string(),
+            value => (value / self.factor).to_string(),
+        }
+    }
+}
+
+/// The `ulimit` command shows or changes the limits on the resources the
+/// shell and the programs it starts can use, like `ulimit -n 256`.
+///
+/// `-S` works on the soft limit, which processes can raise up to the hard
+/// limit, and `-H` on the hard limit, which can only be lowered. A new limit
+/// changes both unless one is picked. Without a resource, it is `-f`, the
+/// size of the files the shell can write.
+pub struct Ulimit {
+    args: Vec<String>,
+}
+
+impl Ulimit {
+    /// Create a new `Ulimit` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `ulimit` command. `-a` shows all the limits.
+    pub fn run(self, io: &mut Io) -> Result<i32> {
+        let (mut soft, mut hard, mut all) = (false, false, false);
+        let mut limits = Vec::new();
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags[1..].chars() {
+                match flag {
+                    'S' => soft = true,
+                    'H' => hard = true,
+                    'a' => all = true,
+                    flag => match LIMITS.iter().find(|limit| limit.flag == flag) {
+                        Some(limit) => limits.push(limit),
+                        None => return Err(format!("ulimit: -{flag}: invalid option").into()),
+                    },
+                }
+            }
+        }
+        let value = args.next();
+        if args.next().is_some() {
+            return Err("ulimit: too many arguments".into());
+        }
+        if all && value.is_some() {
+            // `-a` only shows the limits, so a new limit can't go with it.
+            let flags: String = LIMITS.iter().map(|limit| limit.flag).collect();
+            writeln!(
+                io.stderr,
+                "rush: ulimit: usage: ulimit [-SHa{flags}] [limit]"
+            )?;
+            return Ok(2);
+        }
+        if all {
+            limits = LIMITS.iter().collect();
+        } else if limits.is_empty() {
+            limits.extend(LIMITS.iter().find(|limit| limit.flag == 'f'));
+        }
+
+        let Some(value) = value else {
+            for limit in &limits {
+                let current = sys::getrlimit(limit.resource)
+                    .map_err(|e| format!("ulimit: {}: {}", limit.description, error_message(&e)))?;
+                let current = match hard {
+                    true => limit.show(current.rlim_max),
+                    false => limit.show(current.rlim_cur),
+                };
+                if limits.len() == 1 {
+                    writeln!(io.stdout, "{current}")?;
+                    continue;
+                }
+                let unit = match limit.unit {
+                    Some(unit) => format!("({unit}, -{}) ", limit.flag),
+                    None => format!("(-{}) ", limit.flag),
+                };
+                writeln!(io.stdout, "{:<20} {unit:>20}{current}", limit.description)?;
+            }
+            return Ok(0);
+        };
+
+        for limit in limits {
+            let error = |message: String| format!("ulimit: {}: {message}", limit.description);
+            let mut current =
+                sys::getrlimit(limit.resource).map_err(|e| error(error_message(&e)))?;
+            let new = match value.as_str() {
+                "unlimited" => libc::RLIM_INFINITY,
+                "soft" => current.rlim_cur,
+                "hard" => current.rlim_max,
+                value => value
+                    .parse::<libc::rlim_t>()
+                    .ok()
+                    .and_then(|value| value.checked_mul(limit.factor))
+                    .ok_or_else(|| format!("ulimit: {value}: invalid number"))?,
+            };
+            if soft || !hard {
+                current.rlim_cur = new;
+            }
+            if hard || !soft {
+                current.rlim_max = new;
+            }
+            sys::setrlimit(limit.resource, &current)
+                .map_err(|e| error(format!("cannot modify limit: {}", error_message(&e))))?;
+        }
+        Ok(0)
+    }
+}
+
+/// The `umask` command shows or changes the permissions that new files
+/// don't get, like `umask 022` or `umask g-w`.
+///
+/// The mask is shown in octal, or as the permissions that are allowed with
+/// `-S`, like `u=rwx,g=rx,o=rx`. `-p` shows it as a `umask` command.
+pub struct Umask {
+    args: Vec<String>,
+}
+
+impl Umask {
+    /// Create a new `Umask` command.
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            args: args.to_vec(),
+        }
+    }
+
+    /// Run the `umask` command.
+    pub fn run(self, io: &mut Io) -> Result<i32> {
+        let (mut symbolic, mut print) = (false, false);
+        let mut args = self.args.iter().peekable();
+        while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+            for flag in flags[1..].chars() {
+                match flag {
+                    'S' => symbolic = true,
+                    'p' => print = true,
+                    _ => return Err(format!("umask: -{flag}: invalid option").into()),
+                }
+            }
+        }
+        // Reading the mask means setting it, so it is set back right away.
+        let mask = sys::umask(0);
+        sys::umask(mask);
+
+        let Some(mode) = args.next() else {
+            let shown = match symbolic {
+                true => Self::symbolic(mask),
+                false => format!("{mask:04o}"),
+            };
+            match print {
+                true => writeln!(
+                    io.stdout,
+                    "umask {}{shown}",
+                    if symbolic { "-S " } else { "" }
+                )?,
+                false => writeln!(io.stdout, "{shown}")?,
+            }
+            return Ok(0);
+        };
+        let new = match mode.chars().all(|c| c.is_ascii_digit()) {
+            true => libc::mode_t::from_str_radix(mode, 8)
+                .ok()
+                .filter(|mask| *mask <= 0o777)
+                .ok_or_else(|| format!("umask: {mode}: octal number out of range"))?,
+            false => Self::parse_symbolic(mode, mask)
+                .ok_or_else(|| format!("umask: {mode}: invalid symbolic mode"))?,
+        };
+        sys::umask(new);
+        if symbolic {
+            writeln!(io.stdout, "{}", Self::symbolic(new))?;
+        }
+        Ok(0)
+    }
+
+    /// Show the permissions `mask` allows, like `u=rwx,g=rx,o=rx`.
+    fn symbolic(mask: libc::mode_t) -> String {
+        let allowed = !mask;
+        let classes = [('u', 6), ('g', 3), ('o', 0)].map(|(class, shift)| {
+            let perms = [('r', 4), ('w', 2), ('x', 1)]
+                .into_iter()
+                .filter(|(_, bit)| allowed >> shift & bit != 0)
+                .map(|(perm, _)| perm);
+            format!("{class}={}", perms.collect::<String>())
+        });
+        classes.join(",")
+    }
+
+    /// Apply a symbolic mode like `u=rwx,g-w,o=` to the permissions `mask`
+    /// allows, and return the new mask.
+    ///
+    /// Each clause names classes of users (`a`, or all of them if there is
+    /// none), then an operator and permissions, which can repeat like `u+r-w`.
+    fn parse_symbolic(mode: &str, mask: libc::mode_t) -> Option<libc::mode_t> {
+        let mut allowed = !mask & 0o777;
+        for clause in mode.split(',') {
+            let mut chars = clause.chars().peekable();
+            let mut who = 0;
+            while let Some(class) = chars.next_if(|c| "ugoa".contains(*c)) {
+                who |= match class {
+                    'u' => 0o700,
+                    'g' => 0o070,
+                    'o' => 0o007,
+                    _ => 0o777,
+                };
+            }
+            if who == 0 {
+                who = 0o777;
+            }
+            // There has to be at least one operator.
+            chars.peek()?;
+            while let Some(op) = chars.next() {
+                let mut perms = 0;
+                while let Some(perm) = chars.next_if(|c| !"+-=".contains(*c)) {
+                    perms |= match perm {
+                        'r' => 0o444,
+                        'w' => 0o222,
+                        'x' => 0o111,
+                        _ => return None,
+                    };
+                }
+                let perms = perms & who;
+                match op {
+                    '+' => allowed |= perms,
+                    '-' => allowed &= !perms,
+                    '=' => allowed = allowed & !who | perms,
+                    _ => return None,
+                }
+            }
+        }
+        Some(!allowed & 0o777)
+    }
+}
+
+/// The `source` command (or `.`) runs the commands of a file in the shell,
+/// so they can change it, like an rc file does.
+pub struct Source {
+    path: Option<PathBuf>,
+}
+
+impl Source {
+    /// Create a new `Source` command.
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path }
+    }
+
+    /// Run the `source` command. Its status is the status of the last command of the file.
+    pub fn run(self, shell: &mut Shell) -> Result<i32> {
+        let path = self.path.ok_or("source: filename argument required")?;
+        let script = std::fs::read_to_string(&path)
+            .map_err(|e| format!("source: {}: {}", path.display(), error_message(&e)))?;
+        shell.run_script(&script);
+        shell.run_trap(Event::Return);
+        Ok(shell.last_status)
+    }
+}
@@ -0,0 +1,654 @@
+//! Run the [`Chain`]s produced by the parser.
+//!
+//! All the commands of a pipeline are started at once and connected with
+//! pipes, so data streams from one command to the next while they run.
+//! That's what makes `yes | head -n 3` finish, even though `yes` never stops.
+//! Each pipeline is a [`Job`], which the shell waits for unless it runs in
+//! the background.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, IntoRawFd, OwnedFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::time::Instant;
+
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+use crate::builtins::{self, Io};
+use crate::expand;
+use crate::hash::{self, CommandHash};
+use crate::jobs::{Job, JobSetup, Process};
+use crate::parser::{self, Chain, Cmd, Element, RedirectKind};
+use crate::redirect::{self, error_message, Redirections};
+use crate::time::{self, Usage};
+use crate::traps::Event;
+use crate::{sys, Shell};
+
+/// The commands that are built into the shell.
+const BUILTINS: &[&str] = &[
+    ".", "bg", "builtin", "cd", "command", "disown", "exec", "exit", "fg", "hash", "history",
+    "jobs", "kill", "set", "shopt", "source", "trap", "type", "ulimit", "umask", "wait",
+];
+
+impl Chain {
+    /// Run the pipelines of the chain, skipping the ones `&&` and `||` rule out.
+    ///
+    /// Returns the status of the last pipeline that ran. Traps run between
+    /// the pipelines, and `exit` stops the chain, like a failure with `set -e`.
+    pub fn run(self, shell: &mut Shell) -> ExitStatus {
+        if self.background {
+            let status = self.run_in_background(shell);
+            shell.last_status = status_code(status);
+            return status;
+        }
+
+        let mut status = exit_status(0);
+        let mut pipeline = Vec::new();
+        // `false && a || b` skips `a`, but still runs `b`.
+        let mut run_next = true;
+        let mut elements = self.elements.into_iter();
+        loop {
+            let element = elements.next();
+            match element {
+                Some(Element::Cmd(cmd)) => {
+                    pipeline.push(cmd);
+                    continue;
+                }
+                Some(Element::Pipe) => continue,
+                _ => {}
+            }
+            if run_next && !pipeline.is_empty() {
+                shell.run_trap(Event::Debug);
+                status = run_pipeline(shell, std::mem::take(&mut pipeline), false);
+                shell.last_status = status_code(status);
+                // Like in other shells, a failure that `&&` or `||` checks is not an error.
+                if element.is_none() && !status.success() {
+                    shell.run_trap(Event::Err);
+                    if shell.options.errexit {
+                        shell.exiting.get_or_insert(shell.last_status);
+                    }
+                }
+                shell.run_signal_traps();
+                if shell.exiting.is_some() {
+                    return status;
+                }
+            }
+            pipeline.clear();
+            match element {
+                Some(Element::And) => run_next = status.success(),
+                Some(Element::Or) => run_next = !status.success(),
+                _ => return status,
+            }
+        }
+    }
+
+    /// Start the chain as a background job, without waiting for it.
+    fn run_in_background(mut self, shell: &mut Shell) -> ExitStatus {
+        let is_pipeline = self
+            .elements
+            .iter()
+            .all(|element| matches!(element, Element::Cmd(_) | Element::Pipe));
+        if is_pipeline {
+            let cmds: Vec<_> = self
+                .elements
+                .into_iter()
+                .filter_map(|element| match element {
+                    Element::Cmd(cmd) => Some(cmd),
+                    _ => None,
+                })
+                .collect();
+            return run_pipeline(shell, cmds, true);
+        }
+
+        // `a && b &` has to decide what to run next while in the background,
+        // so a copy of the shell runs the whole chain as one job.
+        let mut job = Job::new(self.to_string());
+        let setup = shell.jobs.setup(&job, false);
+        self.background = false;
+        let process = fork(setup, || {
+            shell.jobs.enter_subshell();
+            shell.traps.enter_subshell();
+            status_code(self.run(shell))
+        });
+        job.push(process, shell.jobs.monitor());
+        shell.jobs.add(job);
+        exit_status(0)
+    }
+}
+
+/// Run the commands of a pipeline like `a | b | c`, and wait for all of them
+/// unless the pipeline runs in the `background`.
+///
+/// The status of the pipeline is the status of its last command, or of the
+/// last one that failed with `set -o pipefail`. The status of each command
+/// goes to the `PIPESTATUS` array.
+///
+/// With the `time` keyword in front, the time the pipeline took is reported
+/// once it is done. With `coproc`, it starts as a coprocess.
+fn run_pipeline(shell: &mut Shell, mut cmds: Vec<Cmd>, background: bool) -> ExitStatus {
+    if let Some(name) = cmds.first_mut().and_then(Cmd::take_coproc) {
+        return start_coproc(shell, cmds, &name);
+    }
+    let timed = cmds.first_mut().and_then(Cmd::take_time);
+    let start = Instant::now();
+    shell.jobs.usage = Usage::default();
+    let (status, statuses) = match &cmds[..] {
+        // A lone `time` only reports that nothing happened.
+        [cmd] if timed.is_some() && cmd.is_empty() => (exit_status(0), vec![exit_status(0)]),
+        _ => run_commands(shell, &cmds, background, None),
+    };
+    if let Some((posix, verbose)) = timed.filter(|_| !background) {
+        let mut format = match posix {
+            true => time::POSIX_FORMAT.to_string(),
+            false => shell
+                .variables
+                .get("TIMEFORMAT")
+                .unwrap_or_else(|| time::DEFAULT_FORMAT.to_string()),
+        };
+        if verbose {
+            format.push_str(time::VERBOSE_FORMAT);
+        }
+        // Like in bash, an empty format prints nothing at all.
+        if !format.is_empty() {
+            eprintln!(
+                "{}",
+                time::format(&format, start.elapsed(), &shell.jobs.usage)
+            );
+        }
+    }
+    let codes = statuses
+        .iter()
+        .map(|status| status_code(*status).to_string());
+    shell.variables.set_array("PIPESTATUS", codes.collect());
+    status
+}
+
+/// Start the commands of a pipeline as a coprocess: a background job the
+/// shell talks to through two pipes.
+///
+/// The array `NAME` holds the file descriptors of the shell's ends: the
+/// output of the job is read from `${NAME[0]}`, and its input is written to
+/// `${NAME[1]}`. `NAME_PID` is the pid of the job.
+fn start_coproc(shell: &mut Shell, cmds: Vec<Cmd>, name: &str) -> ExitStatus {
+    if cmds.first().is_none_or(Cmd::is_empty) {
+        eprintln!("rush: coproc: a command is needed");
+        return exit_status(2);
+    }
+    let pipes = io::pipe().and_then(|input| Ok((input, io::pipe()?)));
+    let ((input_reader, input_writer), (output_reader, output_writer)) = match pipes {
+        Ok(pipes) => pipes,
+        Err(e) => {
+            eprintln!("rush: coproc: {}", error_message(&e));
+            return exit_status(1);
+        }
+    };
+    let pipes = (input_reader.into(), output_writer.into());
+    let (status, _) = run_commands(shell, &cmds, true, Some(pipes));
+
+    // The ends of the shell stay close-on-exec, so no other program keeps
+    // the input of the job open, and it sees the end of its input once the
+    // shell closes `${NAME[1]}`. A redirection like `>&${NAME[1]}` still
+    // works, because it duplicates them.
+    let fds = [output_reader.as_raw_fd(), input_writer.as_raw_fd()]
+        .map(|fd| sys::dup_at_least(fd, redirect::SHELL_FD_BASE).map(IntoRawFd::into_raw_fd));
+    let fds: Vec<RawFd> = match fds.into_iter().collect() {
+        Ok(fds) => fds,
+        Err(e) => {
+            eprintln!("rush: coproc: {}", error_message(&e));
+            return exit_status(1);
+        }
+    };
+    let fds = fds.iter().map(|fd| fd.to_string());
+    shell.variables.set_array(name, fds.collect());
+    if let Some(pid) = shell.jobs.last_pid() {
+        shell.variables.set(&format!("{name}_PID"), pid.to_string());
+    }
+    status
+}
+
+/// Run the commands of a pipeline, and return its status and the status of
+/// each command.
+///
+/// For a coprocess, `coproc` is the input of the first command and the
+/// output of the last one.
+fn run_commands(
+    shell: &mut Shell,
+    cmds: &[Cmd],
+    background: bool,
+    coproc: Option<(OwnedFd, OwnedFd)>,
+) -> (ExitStatus, Vec<ExitStatus>) {
+    // Output the shell has buffered must come before the output of the commands.
+    // If stdout is closed there is nothing to keep in order.
+    let _ = io::stdout().flush();
+
+    let text = cmds.iter().map(Cmd::to_string).collect::<Vec<_>>();
+    let mut cmds: Vec<Cmd> = match cmds.iter().map(|cmd| cmd.expand(shell)).collect() {
+        Ok(cmds) => cmds,
+        Err(message) => {
+            let status = expansion_failed(shell, &message);
+            return (status, vec![status]);
+        }
+    };
+    for cmd in &mut cmds {
+        let result = cmd
+            .skip_prefixes()
+            .and_then(|()| cmd.open_variable_fds(shell));
+        if let Err(message) = result {
+            eprintln!("rush: {message}");
+            return (exit_status(1), vec![exit_status(1)]);
+        }
+    }
+    if shell.options.xtrace {
+        cmds.iter().for_each(|cmd| cmd.trace());
+    }
+
+    // A builtin on its own runs in the shell, so `cd` and `set` can change it.
+    if let [cmd] = &cmds[..] {
+        if cmd.is_builtin() && !background {
+            let status = cmd.run_builtin(shell);
+            return (status, vec![status]);
+        }
+    }
+
+    let mut job = Job::new(text.join(" | "));
+    job.pipefail = shell.options.pipefail;
+    // Without job control, a background job would compete with the shell
+    // for its input, so it reads from `/dev/null` instead (like in bash).
+    let (mut stdin, mut last_stdout) = coproc.unzip();
+    if stdin.is_none() && background && !shell.jobs.interactive() {
+        stdin = File::open("/dev/null").ok().map(OwnedFd::from);
+    }
+    for (i, cmd) in cmds.iter().enumerate() {
+        let pipe = if i + 1 == cmds.len() {
+            last_stdout.take().map(|stdout| (None, stdout))
+        } else {
+            match io::pipe() {
+                Ok((reader, writer)) => Some((Some(reader.into()), writer.into())),
+                Err(e) => {
+                    eprintln!("rush: {}", error_message(&e));
+                    job.push(Process::Done(exit_status(1)), false);
+                    break;
+                }
+            }
+        };
+        let (next_stdin, stdout) = pipe.unzip();
+        let next_stdin = next_stdin.flatten();
+        let setup = shell.jobs.setup(&job, !background);
+        let process = cmd.start(shell, setup, stdin.take(), stdout, next_stdin.as_ref());
+        job.push(process, shell.jobs.monitor());
+        // The shell keeps only the read end, for the next command.
+        stdin = next_stdin;
+    }
+
+    if background {
+        shell.jobs.add(job);
+        (exit_status(0), vec![exit_status(0)])
+    } else {
+        shell.jobs.run_foreground(job)
+    }
+}
+
+/// Report an error like `name: unbound variable`. The command doesn't run,
+/// and like in other shells, a script stops there.
+fn expansion_failed(shell: &mut Shell, message: &str) -> ExitStatus {
+    eprintln!("rush: {message}");
+    if !shell.jobs.interactive() {
+        shell.exiting = Some(1);
+    }
+    exit_status(1)
+}
+
+/// Run `child` in a new child process that joins a job with `setup`,
+/// and exit with the code it returns.
+fn fork(setup: JobSetup, child: impl FnOnce() -> i32) -> Process {
+    // SAFETY: the shell doesn't start threads.
+    match unsafe { sys::fork() } {
+        Ok(0) => {
+            let code = match setup.apply() {
+                Ok(()) => child(),
+                Err(e) => {
+                    eprintln!("rush: {}", error_message(&e));
+                    1
+                }
+            };
+            let _ = io::stdout().flush();
+            sys::exit_now(code)
+        }
+        Ok(pid) => Process::Running(pid),
+        Err(e) => {
+            eprintln!("rush: {}", error_message(&e));
+            Process::Done(exit_status(1))
+        }
+    }
+}
+
+impl Cmd {
+    /// Whether there is nothing to run, not even a redirection.
+    fn is_empty(&self) -> bool {
+        self.binary.is_empty() && self.args.is_empty() && self.redirects.is_empty()
+    }
+
+    /// Take the `coproc` keyword and the name of the coprocess off the front
+    /// of the command, and return the name.
+    ///
+    /// Like in bash, the name is `COPROC` unless one is given. As there are
+    /// no compound commands, a name is told apart from the command by not
+    /// being something the shell can run, like in `coproc CALC bc`.
+    fn take_coproc(&mut self) -> Option<String> {
+        if self.binary != "coproc" {
+            return None;
+        }
+        let named = match &self.args[..] {
+            [name, _, ..] => {
+                parser::is_name(name) && !is_builtin(name) && hash::search(name).is_none()
+            }
+            _ => false,
+        };
+        let name = match named {
+            true => self.args.remove(0),
+            false => "COPROC".to_string(),
+        };
+        self.binary = match self.args.is_empty() {
+            true => String::new(),
+            false => self.args.remove(0),
+        };
+        Some(name)
+    }
+
+    /// Take the `time` keyword and its options off the front of the command.
+    ///
+    /// Returns whether there was one, with its options: `-p` for the POSIX
+    /// format, and `-v` to add the memory used.
+    fn take_time(&mut self) -> Option<(bool, bool)> {
+        if self.binary != "time" {
+            return None;
+        }
+        let (mut posix, mut verbose) = (false, false);
+        while let Some(flags) = self.args.first().and_then(|arg| arg.strip_prefix('-')) {
+            match flags {
+                "-" => {}
+                flags if !flags.is_empty() && flags.chars().all(|c| "pv".contains(c)) => {
+                    posix |= flags.contains('p');
+                    verbose |= flags.contains('v');
+                }
+                _ => break,
+            }
+            self.args.remove(0);
+        }
+        self.binary = match self.args.is_empty() {
+            true => String::new(),
+            false => self.args.remove(0),
+        };
+        Some((posix, verbose))
+    }
+
+    fn is_builtin(&self) -> bool {
+        is_builtin(&self.binary)
+    }
+
+    /// Skip `command` or `builtin` in front of the command.
+    ///
+    /// They make other shells skip functions and aliases, which this one
+    /// doesn't have, so the command runs as usual. `builtin` still checks
+    /// that it is a builtin, and `command -v` is a builtin of its own.
+    fn skip_prefixes(&mut self) -> Result<(), String> {
+        loop {
+            let Some(next) = self.args.first() else {
+                return Ok(());
+            };
+            match self.binary.as_str() {
+                "command" if !next.starts_with('-') => {}
+                "builtin" if !is_builtin(next) => {
+                    return Err(format!("builtin: {next}: not a shell builtin"));
+                }
+                "builtin" => {}
+                _ => return Ok(()),
+            }
+            self.binary = self.args.remove(0);
+        }
+    }
+
+    /// Print the command for `set -x`, after the value of `PS4`.
+    fn trace(&self) {
+        if self.binary.is_empty() {
+            return;
+        }
+        let prefix = std::env::var("PS4").unwrap_or_else(|_| "+ ".to_string());
+        let words = std::iter::once(&self.binary)
+            .chain(&self.args)
+            .map(|word| expand::quote(word))
+            .collect::<Vec<_>>();
+        eprintln!("{prefix}{}", words.join(" "));
+    }
+
+    /// Start the command as a part of a pipeline, in a process that joins
+    /// its job with `setup`.
+    ///
+    /// `stdin` and `stdout` are the pipes that connect it to its neighbours.
+    /// `pipe_end` is the read end of `stdout`, which the shell keeps for the
+    /// next command. Without pipes or redirections, the command uses the
+    /// terminal of the shell directly, so programs like `vim` or `less` work.
+    fn start(
+        &self,
+        shell: &mut Shell,
+        setup: JobSetup,
+        stdin: Option<OwnedFd>,
+        stdout: Option<OwnedFd>,
+        pipe_end: Option<&OwnedFd>,
+    ) -> Process {
+        let redirections = match self.redirections(shell, stdin, stdout) {
+            Ok(redirections) => redirections,
+            Err(message) => {
+                eprintln!("rush: {message}");
+                return Process::Done(exit_status(1));
+            }
+        };
+
+        match self.binary.as_str() {
+            // Only redirections, like `> out.txt`: the files are created, but nothing runs.
+            "" => Process::Done(exit_status(0)),
+            _ if self.is_builtin() => fork(setup, || {
+                // Holding the read end of its own output would keep the builtin
+                // from noticing that the next command exited.
+                if let Some(fd) = pipe_end {
+                    let _ = sys::close(fd.as_raw_fd());
+                }
+                // Like in bash, the builtin can't change the shell then:
+                // `cd /tmp | cat` stays in the current directory.
+                status_code(self.run_builtin_with(shell, redirections))
+            }),
+            _ => self.spawn(&mut shell.hash, setup, redirections),
+        }
+    }
+
+    /// Open the redirections with a variable, like `{fd}<file`, and set their
+    /// variables. The file descriptors belong to the shell and stay open after
+    /// the command, so they are taken out of its redirections.
+    fn open_variable_fds(&mut self, shell: &mut Shell) -> Result<(), String> {
+        let (vars, redirects) = std::mem::take(&mut self.redirects)
+            .into_iter()
+            .partition::<Vec<_>, _>(|redirect| redirect.var.is_some());
+        self.redirects = redirects;
+        for redirect in vars {
+            let name = redirect.var.as_deref().unwrap_or_default();
+            if redirect.kind == RedirectKind::Duplicate && redirect.target == "-" {
+                // `{fd}>&-` closes the file descriptor the variable holds.
+                let fd = shell.variables.get(name).and_then(|fd| fd.parse().ok());
+                let fd = fd.ok_or_else(|| format!("{name}: ambiguous redirect"))?;
+                sys::close(fd).map_err(|e| format!("{fd}: {}", error_message(&e)))?;
+                continue;
+            }
+            let fd = redirect::open_persistent(&redirect, shell.options.noclobber)?;
+            shell.variables.set(name, fd.to_string());
+        }
+        Ok(())
+    }
+
+    /// Open the redirections of the command, after connecting it to its pipes.
+    fn redirections(
+        &self,
+        shell: &Shell,
+        stdin: Option<OwnedFd>,
+        stdout: Option<OwnedFd>,
+    ) -> Result<Redirections, String> {
+        let mut redirections = Redirections::open(&self.redirects, shell.options.noclobber)?;
+        for (fd, pipe) in [(0, stdin), (1, stdout)] {
+            if let Some(pipe) = pipe {
+                redirections = redirections
+                    .with_pipe(fd, pipe)
+                    .map_err(|e| error_message(&e))?;
+            }
+        }
+        Ok(redirections)
+    }
+
+    /// Run a builtin inside the shell, with its redirections applied.
+    fn run_builtin(&self, shell: &mut Shell) -> ExitStatus {
+        match Redirections::open(&self.redirects, shell.options.noclobber) {
+            Ok(redirections) => self.run_builtin_with(shell, redirections),
+            Err(message) => {
+                eprintln!("rush: {message}");
+                exit_status(1)
+            }
+        }
+    }
+
+    fn run_builtin_with(&self, shell: &mut Shell, redirections: Redirections) -> ExitStatus {
+        let saved = match redirections.apply_in_shell() {
+            Ok(saved) => saved,
+            Err(e) => {
+                eprintln!("rush: {}", error_message(&e));
+                return exit_status(1);
+            }
+        };
+
+        let mut io = Io::new();
+        let result = match self.binary.as_ref() {
+            // Without a command, `builtin` does nothing.
+            "builtin" => Ok(0),
+            "command" => builtins::Command::new(&self.args).run(&mut shell.hash, &mut io),
+            "cd" => builtins::Cd::new(&self.args).run(&mut io),
+            "disown" => builtins::Disown::new(&self.args).run(&mut shell.jobs, &mut io),
+            "exec" => builtins::Exec::new(&self.args).run(shell, &mut io),
+            "exit" => {
+                builtins::Exit::new(&self.args, shell.last_status).run(&mut shell.exiting, &mut io)
+            }
+            "hash" => builtins::Hash::new(&self.args).run(&mut shell.hash, &mut io),
+            "history" => builtins::History::new().run(&mut io),
+            "jobs" => builtins::Jobs::new(&self.args).run(&mut shell.jobs, &mut io),
+            "kill" => builtins::Kill::new(&self.args).run(&mut shell.jobs, &mut io),
+            "fg" => builtins::Fg::new(self.args.first().cloned()).run(&mut shell.jobs, &mut io),
+            "bg" => builtins::Bg::new(self.args.first().cloned()).run(&mut shell.jobs, &mut io),
+            "source" | "." => {
+                builtins::Source::new(self.args.first().map(PathBuf::from)).run(shell)
+            }
+            "trap" => builtins::Trap::new(&self.args).run(&mut shell.traps, &mut io),
+            "type" => builtins::Type::new(&self.args).run(&mut shell.hash, &mut io),
+            "ulimit" => builtins::Ulimit::new(&self.args).run(&mut io),
+            "umask" => builtins::Umask::new(&self.args).run(&mut io),
+            "wait" => builtins::Wait::new(&self.args).run(&mut shell.jobs, &mut io),
+            "set" => {
+                builtins::Set::new(&self.args).run(&mut shell.options, &mut shell.jobs, &mut io)
+            }
+            "shopt" => builtins::Shopt::new(&self.args).run(&mut shell.options, &mut io),
+            _ => unreachable!("{} is not a builtin", self.binary),
+        };
+        let code = result.unwrap_or_else(|e| {
+            // Errors go to the redirected stderr too, so `cd nope 2>/dev/null` is quiet.
+            let _ = writeln!(io.stderr, "rush: {e}");
+            1
+        });
+
+        // `exec` without a command changes the file descriptors of the shell for good.
+        // It only returns with a command if the program couldn't start, and
+        // then fails, so the redirections are undone like for other builtins.
+        match self.binary.as_str() {
+            "exec" if code == 0 => saved.keep(),
+            _ => drop(saved),
+        }
+        exit_status(code)
+    }
+
+    /// Start an external program.
+    ///
+    /// A program without a `/` in its name is looked up in `$PATH` through
+    /// `hash`, the table of programs the shell already found.
+    fn spawn(
+        &self,
+        hash: &mut CommandHash,
+        setup: JobSetup,
+        redirections: Redirections,
+    ) -> Process {
+        let program = match self.binary.contains('/') {
+            true => PathBuf::from(&self.binary),
+            false => match hash.resolve(&self.binary) {
+                Some(path) => path,
+                None => {
+                    eprintln!("rush: {}: command not found", self.binary);
+                    return Process::Done(exit_status(127));
+                }
+            },
+        };
+        let mut command = Command::new(program);
+        // The program still sees the name it was called with.
+        command.arg0(&self.binary);
+        command.args(&self.args);
+        // This runs first, while stdin may still be the terminal of the shell.
+        // SAFETY: `apply` only calls async-signal-safe functions.
+        unsafe {
+            command.pre_exec(move || setup.apply());
+        }
+        redirections.apply_to(&mut command);
+
+        let child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                // The program was removed since it was found, so look again next time.
+                if e.kind() == io::ErrorKind::NotFound {
+                    hash.remove(&self.binary);
+                }
+                let (message, code) = spawn_error(&self.binary, &e);
+                eprintln!("rush: {}: {message}", self.binary);
+                return Process::Done(exit_status(code));
+            }
+        };
+        Process::Running(child.id() as libc::pid_t)
+    }
+}
+
+/// Check whether the command `name` is built into the shell.
+pub fn is_builtin(name: &str) -> bool {
+    BUILTINS.contains(&name)
+}
+
+/// Describe why the program `binary` couldn't be started, with the status
+/// other shells use: 127 if it wasn't found, and 126 if it can't be run.
+pub fn spawn_error(binary: &str, e: &io::Error) -> (String, i32) {
+    let is_path = binary.contains('/');
+    match e.kind() {
+        io::ErrorKind::NotFound if !is_path => ("command not found".to_string(), 127),
+        io::ErrorKind::NotFound => (error_message(e), 127),
+        // `execve` fails with `EACCES` for directories, which is confusing.
+        _ if is_path && Path::new(binary).is_dir() => ("Is a directory".to_string(), 126),
+        _ => (error_message(e), 126),
+    }
+}
+
+/// The status code of a command, like `$?` in other shells.
+///
+/// A command that was killed or stopped by a signal has the status 128 plus
+/// the number of the signal, like 130 for Ctrl-C (`SIGINT` is 2).
+pub fn status_code(status: ExitStatus) -> i32 {
+    match (status.code(), status.signal(), status.stopped_signal()) {
+        (Some(code), _, _) => code,
+        (None, Some(signal), _) | (None, None, Some(signal)) => 128 + signal,
+        (None, None, None) => 1,
+    }
+}
+
+/// Build the [`ExitStatus`] of a process that exited with `code`.
+fn exit_status(code: i32) -> ExitStatus {
+    // On Unix, the exit code is stored in the second byte of the raw status.
+    ExitStatus::from_raw(code << 8)
+}
@@ -0,0 +1,316 @@
+//! Expand the words of a command: parameters like `$?` and patterns like `*.txt`.
+//!
+//! Expansion happens right before a command runs, not when the line is
+//! parsed, so `false; echo $?` sees the status of `false`. The parser marks
+//! the characters that were quoted (see [`push_literal`]), which is how
+//! `'$HOME'` and `"*"` stay as they are.
+//!
+//! To keep things simple, the value of a parameter is never split into
+//! several words or expanded as a pattern, as if it was always quoted.
+
+use std::borrow::Cow;
+
+use crate::parser::{push_literal, unescape, Cmd, Redirect};
+use crate::Shell;
+
+impl Cmd {
+    /// Expand the words of the command, and the targets of its redirections.
+    ///
+    /// The error is a message for the user, like `name: unbound variable`.
+    pub fn expand(&self, shell: &Shell) -> Result<Cmd, String> {
+        let mut words = Vec::new();
+        for word in std::iter::once(&self.binary)
+            .filter(|binary| !binary.is_empty())
+            .chain(&self.args)
+        {
+            words.extend(expand_word(word, shell)?);
+        }
+        let redirects = self
+            .redirects
+            .iter()
+            .map(|redirect| {
+                // Patterns aren't expanded here: a redirection needs exactly one file.
+                Ok(Redirect {
+                    fd: redirect.fd,
+                    kind: redirect.kind,
+                    target: unescape(&expand_parameters(&redirect.target, shell)?),
+                    var: redirect.var.clone(),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        let mut words = words.into_iter();
+        Ok(Cmd {
+            binary: words.next().unwrap_or_default(),
+            args: words.collect(),
+            redirects,
+        })
+    }
+}
+
+/// Expand one word into the words it stands for. A pattern can match
+/// several files, or none at all with `nullglob`.
+fn expand_word(word: &str, shell: &Shell) -> Result<Vec<String>, String> {
+    let pattern = expand_parameters(word, shell)?;
+    if !shell.options.noglob && is_pattern(&pattern) {
+        let paths = glob(&pattern, shell.options.dotglob);
+        if !paths.is_empty() || shell.options.nullglob {
+            return Ok(paths);
+        }
+    }
+    // Like in other shells, a pattern that matches nothing stays as it is.
+    Ok(vec![unescape(&pattern)])
+}
+
+/// Replace the parameters in `word` with their values, which are escaped
+/// so they are not expanded again.
+fn expand_parameters(word: &str, shell: &Shell) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                expanded.push(c);
+                expanded.extend(chars.next());
+                continue;
+            }
+            '$' => {}
+            c => {
+                expanded.push(c);
+                continue;
+            }
+        }
+
+        let name = match chars.peek() {
+            Some('{') => {
+                chars.next();
+                let mut name = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+                if name.is_empty() || !closed {
+                    return Err(format!("${{{name}}}: bad substitution"));
+                }
+                name
+            }
+            Some('?' | '-' | '!') => chars.next().into_iter().collect(),
+            Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                name
+            }
+            // A `$` that doesn't start a parameter is just a `$`.
+            _ => {
+                push_literal(&mut expanded, '$');
+                continue;
+            }
+        };
+        let value = match parameter(shell, &name) {
+            Some(value) => value,
+            None if shell.options.nounset => return Err(format!("{name}: unbound variable")),
+            None => String::new(),
+        };
+        for c in value.chars() {
+            push_literal(&mut expanded, c);
+        }
+    }
+    Ok(expanded)
+}
+
+/// The value of the parameter called `name`, if it is set.
+///
+/// Besides the special parameters like `$?`, this can be a variable like
+/// `HOME` or an element of an array like `PIPESTATUS[1]`.
+pub fn parameter(shell: &Shell, name: &str) -> Option<String> {
+    match name {
+        "?" => Some(shell.last_status.to_string()),
+        "!" => shell.jobs.last_pid().map(|pid| pid.to_string()),
+        "-" => {
+            let mut flags = String::new();
+            if shell.jobs.monitor() {
+                flags.push('m');
+            }
+            if shell.jobs.interactive() {
+                flags.push('i');
+            }
+            Some(flags + &shell.options.flags())
+        }
+        _ => shell.variables.get(name),
+    }
+}
+
+/// Check whether `word` has a `*`, `?` or `[` that wasn't quoted.
+fn is_pattern(word: &str) -> bool {
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' | '[' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Find the paths that match `pattern`, sorted by name.
+///
+/// Each component of the path is matched against the entries of the
+/// directories found so far. Hidden files only match a pattern that starts
+/// with a `.`, unless `dotglob` is on.
+fn glob(pattern: &str, dotglob: bool) -> Vec<String> {
+    let mut paths = vec![match pattern.starts_with('/') {
+        true => "/".to_string(),
+        false => String::new(),
+    }];
+    let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+    for (i, component) in components.iter().enumerate() {
+        let is_last = i + 1 == components.len();
+        let mut next = Vec::new();
+        for dir in &paths {
+            if !is_pattern(component) {
+                next.push(format!("{dir}{}", unescape(component)));
+                continue;
+            }
+            let Ok(entries) = std::fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+                continue;
+            };
+            let pattern: Vec<char> = component.chars().collect();
+            let hidden_ok = dotglob || component.starts_with('.');
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if (hidden_ok || !name.starts_with('.')) && matches(&pattern, &name) {
+                    next.push(format!("{dir}{name}"));
+                }
+            }
+        }
+        if !is_last {
+            next.iter_mut().for_each(|path| path.push('/'));
+        }
+        paths = next;
+    }
+
+    if pattern.ends_with('/') {
+        // `*/` only matches directories.
+        paths.retain(|path| std::path::Path::new(path).is_dir());
+        paths.iter_mut().for_each(|path| path.push('/'));
+    }
+    paths.retain(|path| std::fs::symlink_metadata(path).is_ok());
+    paths.sort();
+    paths
+}
+
+/// Check whether `name` matches the shell pattern `pattern`.
+fn matches(pattern: &[char], name: &str) -> bool {
+    let Some((&first, rest)) = pattern.split_first() else {
+        return name.is_empty();
+    };
+    let mut chars = name.chars();
+    let c = chars.next();
+    match first {
+        // Try to match the rest after skipping 0, 1, 2... characters.
+        '*' => name
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([name.len()])
+            .any(|i| matches(rest, &name[i..])),
+        '?' => c.is_some() && matches(rest, chars.as_str()),
+        '[' => match bracket(rest, c) {
+            Some((matched, rest)) => matched && matches(rest, chars.as_str()),
+            // Without a closing `]`, the `[` is an ordinary character.
+            None => c == Some('[') && matches(rest, chars.as_str()),
+        },
+        '\\' => match rest.split_first() {
+            Some((&escaped, rest)) => c == Some(escaped) && matches(rest, chars.as_str()),
+            None => c == Some('\\') && chars.as_str().is_empty(),
+        },
+        first => c == Some(first) && matches(rest, chars.as_str()),
+    }
+}
+
+/// Match `c` against a bracket expression like `[a-z_]` or `[!0-9]`,
+/// starting after the `[`.
+///
+/// Returns whether `c` matched and the rest of the pattern after the `]`,
+/// or `None` if the bracket expression isn't closed.
+fn bracket(pattern: &[char], c: Option<char>) -> Option<(bool, &[char])> {
+    let (negated, mut pattern) = match pattern.split_first() {
+        Some(('!' | '^', rest)) => (true, rest),
+        _ => (false, pattern),
+    };
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let (&start, rest) = pattern.split_first()?;
+        // A `]` right after the `[` is part of the set, like in `[]]`.
+        if start == ']' && !first {
+            return Some((c.is_some() && matched != negated, rest));
+        }
+        first = false;
+        let (start, rest) = match (start, rest) {
+            ('\\', [escaped, rest @ ..]) => (*escaped, rest),
+            _ => (start, rest),
+        };
+        let (end, rest) = match rest {
+            ['-', end, rest @ ..] if *end != ']' => (*end, rest),
+            _ => (start, rest),
+        };
+        matched |= c.is_some_and(|c| (start..=end).contains(&c));
+        pattern = rest;
+    }
+}
+
+/// Quote `word` for the output of `set -x`, only if it needs it.
+pub fn quote(word: &str) -> Cow<'_, str> {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "%+,-./:=@_^".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        return Cow::Borrowed(word);
+    }
+    Cow::Owned(format!("'{}'", word.replace('\'', r"'\''")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches_str(pattern: &str, name: &str) -> bool {
+        matches(&pattern.chars().collect::<Vec<_>>(), name)
+    }
+
+    #[test]
+    fn patterns_match_names() {
+        assert!(matches_str("*.txt", "notes.txt"));
+        assert!(!matches_str("*.txt", "notes.txt.bak"));
+        assert!(matches_str("a?c", "abc"));
+        assert!(!matches_str("a?c", "ac"));
+        assert!(matches_str("[a-c]x", "bx"));
+        assert!(!matches_str("[!a-c]x", "bx"));
+        assert!(matches_str("[]]", "]"));
+        assert!(matches_str("[", "["));
+        assert!(matches_str(r"\*", "*"));
+        assert!(!matches_str(r"\*", "a"));
+    }
+
+    #[test]
+    fn quoted_characters_are_not_patterns() {
+        assert!(is_pattern("*.rs"));
+        assert!(!is_pattern(r"\*.rs"));
+        assert_eq!(unescape(r"a\$b\\c"), r"a$b\c");
+    }
+
+    #[test]
+    fn words_are_quoted_only_if_needed() {
+        assert_eq!(quote("ls"), "ls");
+        assert_eq!(quote("a b"), "'a b'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote(""), "''");
+    }
+}
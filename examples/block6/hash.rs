@@ -0,0 +1,113 @@
+//! Remember where programs are, so `$PATH` is searched only once for each.
+//!
+//! The table is filled as commands run, and the `hash` builtin shows it.
+//! It is cleared when `$PATH` changes, and an entry is forgotten when its
+//! program is gone, so the next run searches `$PATH` again.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// A program found in `$PATH`.
+struct Entry {
+    path: PathBuf,
+    /// How many times the shell used the entry.
+    hits: usize,
+}
+
+#[derive(Default)]
+pub struct CommandHash {
+    entries: BTreeMap<String, Entry>,
+    /// The value of `$PATH` the entries were found with.
+    path_var: Option<OsString>,
+}
+
+impl CommandHash {
+    /// Find the program called `name`, in the table or else in `$PATH`,
+    /// and count the use.
+    pub fn resolve(&mut self, name: &str) -> Option<PathBuf> {
+        let entry = self.lookup(name)?;
+        entry.hits += 1;
+        Some(entry.path.clone())
+    }
+
+    /// Find the program called `name` and remember it, without counting a
+    /// use, like `hash name` does.
+    pub fn add(&mut self, name: &str) -> Option<PathBuf> {
+        self.lookup(name).map(|entry| entry.path.clone())
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Entry> {
+        let path_var = std::env::var_os("PATH");
+        if path_var != self.path_var {
+            self.entries.clear();
+            self.path_var = path_var;
+        }
+        if !self.entries.contains_key(name) {
+            let path = search(name)?;
+            self.insert(name, path);
+        }
+        self.entries.get_mut(name)
+    }
+
+    /// Where the program `name` is, if it is in the table.
+    pub fn get(&self, name: &str) -> Option<&Path> {
+        self.entries
+            .get(name)
+            .filter(|_| std::env::var_os("PATH") == self.path_var)
+            .map(|entry| entry.path.as_path())
+    }
+
+    /// Remember that `name` is the program at `path`, like `hash -p path name`.
+    pub fn insert(&mut self, name: &str, path: PathBuf) {
+        self.entries
+            .insert(name.to_string(), Entry { path, hits: 0 });
+    }
+
+    /// Forget where `name` is, because its program is gone.
+    pub fn remove(&mut self, name: &str) {
+        self.entries.remove(name);
+    }
+
+    /// Forget all programs, like `hash -r`.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write the table like `hash` does, with the number of uses of each program.
+    pub fn list(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "hits\tcommand")?;
+        for entry in self.entries.values() {
+            writeln!(out, "{:4}\t{}", entry.hits, entry.path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Search the directories of `$PATH` for an executable file called `name`.
+pub fn search(name: &str) -> Option<PathBuf> {
+    search_all(name).into_iter().next()
+}
+
+/// Find all the executable files called `name` in the directories of `$PATH`.
+pub fn search_all(name: &str) -> Vec<PathBuf> {
+    let Some(path_var) = std::env::var_os("PATH") else {
+        return Vec::new();
+    };
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(name))
+        .filter(|path| is_executable(path))
+        .collect()
+}
+
+/// Check whether `path` is a file the user could run.
+pub fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
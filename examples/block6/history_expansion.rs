@@ -0,0 +1,547 @@
+//! csh-style history expansion.
+//!
+//! Before a line is parsed, references to earlier commands are replaced
+//! with (parts of) those commands. A reference has three parts:
+//!
+//! - an *event* that picks a line from the history:
+//!   `!!` (the previous line), `!42`, `!-2`, `!git` (the last line starting
+//!   with `git`) or `!?text?` (the last line containing `text`),
+//! - an optional *word designator* that picks words of that line:
+//!   `:0`, `:2`, `:^`, `:$`, `:*`, `:2-4`, `:2-`, `:2*`
+//!   (the `:` can be left out before `^`, `$` and `*`, as in `!$`),
+//! - optional *modifiers*: `:h`, `:t`, `:r`, `:e`, `:p`, `:s/old/new/`,
+//!   `:gs/old/new/` and `:&`.
+//!
+//! On top of that, `^old^new^` at the start of a line repeats the previous
+//! line with `old` replaced by `new`.
+
+use crate::Result;
+
+/// The result of expanding a line.
+#[derive(Debug, PartialEq)]
+pub struct Expanded {
+    /// The line with all history references replaced.
+    pub line: String,
+    /// Whether the line contained any history reference.
+    pub changed: bool,
+    /// The `:p` modifier was used: print the line, but don't run it.
+    pub print_only: bool,
+}
+
+/// Expand all history references in `line`.
+///
+/// `history` contains the previous lines, oldest first.
+/// Text inside single quotes and `!` escaped with a backslash are left alone.
+pub fn expand(line: &str, history: &[String]) -> Result<Expanded> {
+    let mut expander = Expander {
+        chars: line.chars().collect(),
+        pos: 0,
+        history,
+        print_only: false,
+        last_substitution: None,
+    };
+
+    let mut out = String::new();
+    let mut changed = false;
+    if line.starts_with('^') {
+        out.push_str(&expander.quick_substitution()?);
+        changed = true;
+    }
+
+    let mut quote: Option<char> = None;
+    while let Some(c) = expander.peek() {
+        match (c, quote) {
+            ('\\', None | Some('"')) => {
+                // Keep the escaped character as it is, the parser removes the backslash.
+                out.push(c);
+                expander.pos += 1;
+                if let Some(escaped) = expander.peek() {
+                    out.push(escaped);
+                    expander.pos += 1;
+                }
+                continue;
+            }
+            ('\'' | '"', None) => quote = Some(c),
+            ('\'', Some('\'')) | ('"', Some('"')) => quote = None,
+            ('!', None | Some('"')) if expander.starts_reference(quote) => {
+                out.push_str(&expander.reference()?);
+                changed = true;
+                continue;
+            }
+            _ => {}
+        }
+        out.push(c);
+        expander.pos += 1;
+    }
+
+    Ok(Expanded {
+        line: out,
+        changed,
+        print_only: expander.print_only,
+    })
+}
+
+struct Expander<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    history: &'a [String],
+    print_only: bool,
+    /// The `old` and `new` strings of the last `:s` modifier, reused by `:&`.
+    last_substitution: Option<(String, String)>,
+}
+
+impl Expander<'_> {
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    /// The text between `start` and the current position, used in error messages.
+    fn text_from(&self, start: usize) -> String {
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Check whether the `!` at the current position starts a history reference.
+    ///
+    /// Like in bash, a `!` followed by a blank, `=` or `(` is taken literally,
+    /// and so is the `!` of `$!`.
+    fn starts_reference(&self, quote: Option<char>) -> bool {
+        if self.pos > 0 && self.chars[self.pos - 1] == '$' {
+            return false;
+        }
+        match self.peek_at(1) {
+            None => false,
+            Some(c) if c.is_whitespace() || c == '=' || c == '(' => false,
+            Some('"') => quote.is_none(),
+            Some(_) => true,
+        }
+    }
+
+    /// Expand the history reference starting at the `!` at the current position.
+    fn reference(&mut self) -> Result<String> {
+        let start = self.pos;
+        self.pos += 1;
+        let event = self.event(start)?;
+
+        let mut text = event.clone();
+        match (self.peek(), self.peek_at(1)) {
+            (Some(':'), Some(c)) if c.is_ascii_digit() || "^$*-".contains(c) => {
+                self.pos += 1;
+                text = self.word_designator(&event)?;
+            }
+            (Some('^' | '$' | '*'), _) => text = self.word_designator(&event)?,
+            _ => {}
+        }
+
+        self.modifiers(text)
+    }
+
+    /// Find the history line the event designator refers to.
+    fn event(&mut self, start: usize) -> Result<String> {
+        let found = match self.peek() {
+            Some('!') => {
+                self.pos += 1;
+                self.relative_event(1)
+            }
+            Some('-') if self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) => {
+                self.pos += 1;
+                let n = self.number();
+                self.relative_event(n)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let n = self.number();
+                n.checked_sub(1).and_then(|i| self.history.get(i))
+            }
+            Some('?') => {
+                self.pos += 1;
+                let mut needle = String::new();
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    if c == '?' {
+                        break;
+                    }
+                    needle.push(c);
+                }
+                self.history
+                    .iter()
+                    .rev()
+                    .find(|line| line.contains(&needle))
+            }
+            // `!$`, `!^`, `!*` and `!:n` refer to the previous line.
+            Some('^' | '$' | '*' | ':') => self.relative_event(1),
+            _ => {
+                let mut prefix = String::new();
+                while let Some(c) = self.peek() {
+                    if c.is_whitespace() || ":;&|<>()'\"".contains(c) {
+                        break;
+                    }
+                    prefix.push(c);
+                    self.pos += 1;
+                }
+                self.history
+                    .iter()
+                    .rev()
+                    .find(|line| line.starts_with(&prefix))
+            }
+        };
+
+        found
+            .cloned()
+            .ok_or_else(|| format!("{}: event not found", self.text_from(start)).into())
+    }
+
+    /// Get the line `n` lines back in the history.
+    fn relative_event(&self, n: usize) -> Option<&String> {
+        self.history
+            .len()
+            .checked_sub(n)
+            .and_then(|i| self.history.get(i))
+    }
+
+    fn number(&mut self) -> usize {
+        let mut n: usize = 0;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            n = n.saturating_mul(10).saturating_add(digit as usize);
+            self.pos += 1;
+        }
+        n
+    }
+
+    /// Select words of `event`, like `2-4`, `$` or `*`.
+    fn word_designator(&mut self, event: &str) -> Result<String> {
+        let start = self.pos;
+        let words = split_words(event);
+        let last = words.len().saturating_sub(1);
+
+        let (from, to) = match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                (1, last)
+            }
+            // `-y` is short for `0-y`.
+            Some('-') => (0, self.range_end(last)),
+            _ => {
+                let from = self.word_index(last);
+                match self.peek() {
+                    Some('*') => {
+                        self.pos += 1;
+                        (from, last)
+                    }
+                    Some('-') => (from, self.range_end(last)),
+                    _ => (from, from),
+                }
+            }
+        };
+
+        if from > to {
+            // `x*` and `*` expand to nothing when there are no words left.
+            if self.chars[start..self.pos].ends_with(&['*']) {
+                return Ok(String::new());
+            }
+        } else if to < words.len() {
+            return Ok(words[from..=to].join(" "));
+        }
+        Err(format!(":{}: bad word specifier", self.text_from(start)).into())
+    }
+
+    /// Parse the end of a `x-y` range, starting at the `-`.
+    ///
+    /// `x-` without an end means "up to, but not including, the last word".
+    fn range_end(&mut self, last: usize) -> usize {
+        self.pos += 1;
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '^' || c == '$' => self.word_index(last),
+            // Wrap around for a line with only one word, so the range is empty.
+            _ => last.wrapping_sub(1),
+        }
+    }
+
+    fn word_index(&mut self, last: usize) -> usize {
+        match self.peek() {
+            Some('^') => {
+                self.pos += 1;
+                1
+            }
+            Some('$') => {
+                self.pos += 1;
+                last
+            }
+            _ => self.number(),
+        }
+    }
+
+    /// Apply the modifiers (`:h`, `:s/old/new/`, ...) that follow a reference.
+    fn modifiers(&mut self, mut text: String) -> Result<String> {
+        while self.peek() == Some(':') {
+            let start = self.pos;
+            // A `g` makes the following `s` or `&` replace every occurrence.
+            let global = self.peek_at(1) == Some('g');
+            let modifier = self.peek_at(if global { 2 } else { 1 });
+            match modifier {
+                Some('h') if !global => {
+                    if let Some(slash) = text.rfind('/') {
+                        text.truncate(slash);
+                    }
+                }
+                Some('t') if !global => {
+                    if let Some(slash) = text.rfind('/') {
+                        text.drain(..=slash);
+                    }
+                }
+                Some('r') if !global => {
+                    if let Some(dot) = suffix_start(&text) {
+                        text.truncate(dot);
+                    }
+                }
+                Some('e') if !global => {
+                    if let Some(dot) = suffix_start(&text) {
+                        text.drain(..dot);
+                    }
+                }
+                Some('p') if !global => self.print_only = true,
+                Some('s') => {
+                    self.pos += if global { 3 } else { 2 };
+                    let delimiter = self.peek().ok_or(":s: missing delimiter")?;
+                    self.pos += 1;
+                    self.substitution_pattern(delimiter)?;
+                    text = self.substitute(&text, global, start)?;
+                    continue;
+                }
+                Some('&') => {
+                    self.pos += if global { 3 } else { 2 };
+                    text = self.substitute(&text, global, start)?;
+                    continue;
+                }
+                // Not a modifier, leave the `:` in the line.
+                _ => break,
+            }
+            self.pos += 2;
+        }
+        Ok(text)
+    }
+
+    /// Parse `old/new/` (after the first delimiter) and remember it as the last substitution.
+    fn substitution_pattern(&mut self, delimiter: char) -> Result<()> {
+        let mut old = self.delimited(delimiter);
+        let new = self.delimited(delimiter);
+        if old.is_empty() {
+            // An empty `old` reuses the previous one, like in `:s//new/`.
+            old = match &self.last_substitution {
+                Some((old, _)) => old.clone(),
+                None => return Err(":s: no previous substitution".into()),
+            };
+        }
+        let new = new.replace('&', &old).replace('\u{0}', "&");
+        self.last_substitution = Some((old, new));
+        Ok(())
+    }
+
+    /// Read up to the next (unescaped) `delimiter`, or to the end of the line.
+    ///
+    /// A `\&` is kept as NUL here, so it isn't replaced by `old` afterwards.
+    fn delimited(&mut self, delimiter: char) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                c if c == delimiter => break,
+                '\\' if self.peek() == Some(delimiter) => {
+                    text.push(delimiter);
+                    self.pos += 1;
+                }
+                '\\' if self.peek() == Some('&') => {
+                    text.push('\u{0}');
+                    self.pos += 1;
+                }
+                c => text.push(c),
+            }
+        }
+        text
+    }
+
+    /// Apply the last substitution to `text`.
+    fn substitute(&self, text: &str, global: bool, start: usize) -> Result<String> {
+        let Some((old, new)) = &self.last_substitution else {
+            return Err(format!("{}: no previous substitution", self.text_from(start)).into());
+        };
+        if !text.contains(old.as_str()) {
+            return Err(format!("{}: substitution failed", self.text_from(start)).into());
+        }
+        Ok(if global {
+            text.replace(old.as_str(), new)
+        } else {
+            text.replacen(old.as_str(), new, 1)
+        })
+    }
+
+    /// Expand `^old^new^`, which is short for `!!:s^old^new^`.
+    fn quick_substitution(&mut self) -> Result<String> {
+        self.pos += 1;
+        self.substitution_pattern('^')?;
+        let previous = self
+            .relative_event(1)
+            .cloned()
+            .ok_or_else(|| format!("{}: event not found", self.text_from(0)))?;
+        self.substitute(&previous, false, 0)
+    }
+}
+
+/// Split a history line into words, keeping quoted strings together.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            (c, None) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                continue;
+            }
+            ('\\', _) => {
+                word.push(c);
+                if let Some(escaped) = chars.next() {
+                    word.push(escaped);
+                }
+                continue;
+            }
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            _ => {}
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Find the `.` that starts the suffix of the last path component, if any.
+fn suffix_start(text: &str) -> Option<usize> {
+    let dot = text.rfind('.')?;
+    match text.rfind('/') {
+        Some(slash) if slash > dot => None,
+        _ => Some(dot),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> Vec<String> {
+        [
+            "ls -l /tmp",
+            "git status",
+            "cp src/main.rs /tmp/backup.rs",
+            "echo one two three four",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    fn expand_line(line: &str) -> String {
+        expand(line, &history()).unwrap().line
+    }
+
+    #[test]
+    fn line_without_references_is_unchanged() {
+        let expanded = expand("echo hello!", &history()).unwrap();
+        assert_eq!(
+            expanded,
+            Expanded {
+                line: "echo hello!".to_string(),
+                changed: false,
+                print_only: false
+            }
+        );
+    }
+
+    #[test]
+    fn event_designators_are_expanded() {
+        assert_eq!(expand_line("!!"), "echo one two three four");
+        assert_eq!(expand_line("!1"), "ls -l /tmp");
+        assert_eq!(expand_line("!-3"), "git status");
+        assert_eq!(expand_line("!git"), "git status");
+        assert_eq!(expand_line("!?backup?"), "cp src/main.rs /tmp/backup.rs");
+        assert_eq!(
+            expand_line("sudo !! | less"),
+            "sudo echo one two three four | less"
+        );
+    }
+
+    #[test]
+    fn word_designators_are_expanded() {
+        assert_eq!(expand_line("!$"), "four");
+        assert_eq!(expand_line("!^"), "one");
+        assert_eq!(expand_line("!*"), "one two three four");
+        assert_eq!(expand_line("!!:0"), "echo");
+        assert_eq!(expand_line("!!:2-4"), "two three four");
+        assert_eq!(expand_line("!!:2-"), "two three");
+        assert_eq!(expand_line("!!:-2"), "echo one two");
+        assert_eq!(expand_line("!!:3*"), "three four");
+        assert_eq!(expand_line("vim !cp:1"), "vim src/main.rs");
+    }
+
+    #[test]
+    fn modifiers_are_applied() {
+        assert_eq!(expand_line("!cp:$:h"), "/tmp");
+        assert_eq!(expand_line("!cp:$:t"), "backup.rs");
+        assert_eq!(expand_line("!cp:1:r"), "src/main");
+        assert_eq!(expand_line("!cp:1:e"), ".rs");
+        assert_eq!(expand_line("!!:s/one/1/"), "echo 1 two three four");
+        assert_eq!(
+            expand_line("!cp:gs/rs/txt"),
+            "cp src/main.txt /tmp/backup.txt"
+        );
+        assert_eq!(expand_line("!!:s/two/[&]/"), "echo one [two] three four");
+    }
+
+    #[test]
+    fn quick_substitution_is_expanded() {
+        assert_eq!(expand_line("^four^five^"), "echo one two three five");
+        assert_eq!(expand_line("^four^five"), "echo one two three five");
+    }
+
+    #[test]
+    fn print_modifier_is_reported() {
+        assert!(expand("!!:p", &history()).unwrap().print_only);
+    }
+
+    #[test]
+    fn quoted_and_escaped_references_are_kept() {
+        assert_eq!(expand_line("echo '!!' \\!!"), "echo '!!' \\!!");
+        assert_eq!(
+            expand_line("echo \"!!\""),
+            "echo \"echo one two three four\""
+        );
+        assert_eq!(expand_line("[ ! -e x ]; a!=b"), "[ ! -e x ]; a!=b");
+        assert_eq!(expand_line("wait $!; echo $!x"), "wait $!; echo $!x");
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let error = |line: &str| expand(line, &history()).unwrap_err().to_string();
+        assert_eq!(error("!nope"), "!nope: event not found");
+        assert_eq!(error("!42"), "!42: event not found");
+        assert_eq!(error("!git:5"), ":5: bad word specifier");
+        assert_eq!(error("^nope^yes"), "^nope^yes: substitution failed");
+    }
+
+    #[test]
+    fn blank_history_lines_have_no_words() {
+        let history = ["ls".to_string(), "  ".to_string()];
+        let error = |line: &str| expand(line, &history).unwrap_err().to_string();
+        assert_eq!(error("!!:0"), ":0: bad word specifier");
+        assert_eq!(error("!!:$"), ":$: bad word specifier");
+        assert_eq!(error("!!:0-"), ":0-: bad word specifier");
+        assert_eq!(expand("echo !!:*", &history).unwrap().line, "echo ");
+    }
+}
@@ -0,0 +1,826 @@
+//! Job control: run pipelines in the background, and move them between the
+//! background and the foreground.
+//!
+//! Every pipeline is a job and runs in a process group of its own. The
+//! terminal sends signals like Ctrl-C to its foreground process group only,
+//! so the shell hands the terminal to a job with `tcsetpgrp` while it waits
+//! for it, and takes it back afterwards.
+
+use std::io::{self, IsTerminal, Write};
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+
+use crate::redirect::{error_message, SHELL_FD_BASE};
+use crate::time::Usage;
+use crate::{signals, sys};
+
+/// Signals the shell ignores while it controls the terminal.
+///
+/// `SIGTTOU` and `SIGTTIN` would stop the shell when it uses the terminal
+/// while a job is in the foreground, and `SIGTSTP` (Ctrl-Z) is meant for
+/// the job, not the shell.
+const IGNORED_SIGNALS: &[libc::c_int] = &[libc::SIGTTOU, libc::SIGTTIN, libc::SIGTSTP];
+
+/// A command of a job.
+pub enum Process {
+    /// The command runs in the child process with this pid.
+    Running(libc::pid_t),
+    /// The command was stopped by `signal`, like `SIGTSTP` for Ctrl-Z.
+    Stopped {
+        pid: libc::pid_t,
+        signal: libc::c_int,
+    },
+    /// The command has ended, or never started because of an error.
+    Done(ExitStatus),
+}
+
+impl Process {
+    /// Wait until the process stops or ends.
+    /// With `WNOHANG` in `flags`, only check if it did.
+    ///
+    /// Returns whether the state of the process changed. What it used
+    /// is added to `usage` when it ends.
+    fn wait(&mut self, flags: libc::c_int, usage: &mut Usage) -> bool {
+        let pid = match *self {
+            Process::Running(pid) => pid,
+            // Only `WCONTINUED` notices that someone else continued a stopped process.
+            Process::Stopped { pid, .. } if flags & libc::WCONTINUED != 0 => pid,
+            _ => return false,
+        };
+        match sys::wait4(pid, flags) {
+            Ok(Some((status, used))) => self.update(pid, status, &used, usage),
+            Ok(None) => return false,
+            Err(e) => {
+                eprintln!("rush: {}", error_message(&e));
+                *self = Process::Done(ExitStatus::from_raw(1 << 8));
+            }
+        }
+        true
+    }
+
+    /// Record the new state of the process `pid` from its wait `status`.
+    fn update(
+        &mut self,
+        pid: libc::pid_t,
+        status: libc::c_int,
+        used: &libc::rusage,
+        usage: &mut Usage,
+    ) {
+        *self = if libc::WIFSTOPPED(status) {
+            Process::Stopped {
+                pid,
+                signal: libc::WSTOPSIG(status),
+            }
+        } else if libc::WIFCONTINUED(status) {
+            Process::Running(pid)
+        } else {
+            usage.add(used);
+            Process::Done(ExitStatus::from_raw(status))
+        };
+    }
+}
+
+/// A pipeline that was started by the shell.
+pub struct Job {
+    /// The number users refer to the job with, like the `1` in `fg %1`.
+    pub id: usize,
+    /// The process group of the job, which is the pid of its first process,
+    /// or `0` if no process was started.
+    pub pgid: libc::pid_t,
+    pub processes: Vec<Process>,
+    /// The command line of the job, for `jobs` and `fg`.
+    pub command: String,
+    /// Whether the job fails if any of its commands fails (`set -o pipefail`).
+    pub pipefail: bool,
+    /// The resources its processes used, once they ended.
+    usage: Usage,
+    /// The pids of the processes that were started, which `wait` accepts
+    /// even once they have ended.
+    pids: Vec<libc::pid_t>,
+    /// Whether the job doesn't get `SIGHUP` when the shell exits (`disown -h`).
+    nohup: bool,
+    /// The terminal modes the job had when it was stopped,
+    /// so an editor that was suspended gets its raw mode back.
+    modes: Option<libc::termios>,
+    /// Whether the job stopped or ended since the user was last told about it.
+    changed: bool,
+}
+
+impl Job {
+    pub fn new(command: String) -> Self {
+        Self {
+            id: 0,
+            pgid: 0,
+            processes: Vec::new(),
+            command,
+            pipefail: false,
+            usage: Usage::default(),
+            pids: Vec::new(),
+            nohup: false,
+            modes: None,
+            changed: false,
+        }
+    }
+
+    /// Add a process to the job.
+    ///
+    /// Both the shell and the child move the child into the process group
+    /// of the job, so it is there no matter which one runs first.
+    pub fn push(&mut self, process: Process, monitor: bool) {
+        if let Process::Running(pid) = process {
+            self.pids.push(pid);
+            if self.pgid == 0 {
+                self.pgid = pid;
+            }
+            if monitor {
+                // This fails once the child has executed its program, which is fine.
+                let _ = sys::setpgid(pid, self.pgid);
+            }
+        }
+        self.processes.push(process);
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.processes
+            .iter()
+            .all(|process| matches!(process, Process::Done(_)))
+    }
+
+    /// The status of the job once it's done, which is the status of its last
+    /// command, or of the last one that failed with `pipefail`.
+    pub fn status(&self) -> Option<ExitStatus> {
+        if !self.is_done() {
+            return None;
+        }
+        let statuses = self.statuses();
+        let failed = statuses
+            .iter()
+            .rev()
+            .find(|status| !status.success())
+            .filter(|_| self.pipefail);
+        Some(
+            failed
+                .or(statuses.last())
+                .copied()
+                .unwrap_or(ExitStatus::from_raw(0)),
+        )
+    }
+
+    /// The status of each command of the job, like `PIPESTATUS` shows them.
+    /// A command that didn't end yet counts as a success.
+    pub fn statuses(&self) -> Vec<ExitStatus> {
+        self.processes
+            .iter()
+            .map(|process| match *process {
+                Process::Done(status) => status,
+                Process::Stopped { signal, .. } => stopped_status(signal),
+                Process::Running(_) => ExitStatus::from_raw(0),
+            })
+            .collect()
+    }
+
+    /// Whether the job is stopped: none of its processes runs, but some are stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.stop_signal().is_some()
+            && !self
+                .processes
+                .iter()
+                .any(|process| matches!(process, Process::Running(_)))
+    }
+
+    /// The signal that stopped the job, if any.
+    fn stop_signal(&self) -> Option<libc::c_int> {
+        self.processes
+            .iter()
+            .rev()
+            .find_map(|process| match process {
+                Process::Stopped { signal, .. } => Some(*signal),
+                _ => None,
+            })
+    }
+
+    /// Wait until all the processes of the job have stopped or ended.
+    fn wait(&mut self) {
+        for process in &mut self.processes {
+            process.wait(libc::WUNTRACED, &mut self.usage);
+        }
+    }
+
+    /// Check which processes of the job have stopped, continued or ended, without waiting.
+    fn poll(&mut self) {
+        for process in &mut self.processes {
+            let flags = libc::WNOHANG | libc::WUNTRACED | libc::WCONTINUED;
+            let changed = process.wait(flags, &mut self.usage);
+            // Users are told when a job stops or ends, but not when it continues.
+            if changed && !matches!(process, Process::Running(_)) {
+                self.changed = true;
+            }
+        }
+    }
+
+    /// Send `signal` to the processes of the job: to its process group with
+    /// job control, or else to each process that is still there.
+    fn kill(&self, signal: libc::c_int, monitor: bool) -> io::Result<()> {
+        if monitor {
+            return sys::kill(-self.pgid, signal);
+        }
+        self.processes
+            .iter()
+            .try_for_each(|process| match *process {
+                Process::Running(pid) | Process::Stopped { pid, .. } => sys::kill(pid, signal),
+                Process::Done(_) => Ok(()),
+            })
+    }
+
+    /// Record the new wait `status` of the process `pid`, if it belongs to the job.
+    ///
+    /// Returns whether it did.
+    fn update(&mut self, pid: libc::pid_t, status: libc::c_int, used: &libc::rusage) -> bool {
+        let Some(process) = self.processes.iter_mut().find(|process| match process {
+            Process::Running(running) => *running == pid,
+            Process::Stopped { pid: stopped, .. } => *stopped == pid,
+            Process::Done(_) => false,
+        }) else {
+            return false;
+        };
+        process.update(pid, status, used, &mut self.usage);
+        if !matches!(process, Process::Running(_)) {
+            self.changed = true;
+        }
+        true
+    }
+
+    /// Continue the stopped processes of the job.
+    fn resume(&mut self) -> io::Result<()> {
+        if !self.is_stopped() {
+            return Ok(());
+        }
+        sys::kill(-self.pgid, libc::SIGCONT)?;
+        for process in &mut self.processes {
+            if let Process::Stopped { pid, .. } = *process {
+                *process = Process::Running(pid);
+            }
+        }
+        Ok(())
+    }
+
+    /// Describe the state of the job like `jobs` does: `Running`, `Stopped`, `Done` or `Exit 1`.
+    fn state(&self) -> String {
+        if self.is_stopped() {
+            return match self.stop_signal() {
+                Some(libc::SIGSTOP) => "Stopped (signal)",
+                Some(libc::SIGTTIN) => "Stopped (tty input)",
+                Some(libc::SIGTTOU) => "Stopped (tty output)",
+                _ => "Stopped",
+            }
+            .to_string();
+        }
+        let Some(status) = self.status() else {
+            return "Running".to_string();
+        };
+        match (status.code(), status.signal()) {
+            (Some(0), _) => "Done".to_string(),
+            (Some(code), _) => format!("Exit {code}"),
+            (None, Some(signal)) => describe_signal(signal, status.core_dumped()),
+            (None, None) => "Done".to_string(),
+        }
+    }
+}
+
+/// The raw wait status of a process that was stopped by `signal`.
+fn stopped_status(signal: libc::c_int) -> ExitStatus {
+    ExitStatus::from_raw(signal << 8 | 0x7f)
+}
+
+/// Describe the death of a process from `signal`, like
+/// "Segmentation fault (core dumped)".
+fn describe_signal(signal: libc::c_int, core_dumped: bool) -> String {
+    let description = sys::signal_description(signal);
+    match core_dumped {
+        true => format!("{description} (core dumped)"),
+        false => description,
+    }
+}
+
+/// What a child process does to join its job, right before it runs its command.
+///
+/// This runs between `fork` and `exec`, so it only uses async-signal-safe
+/// system calls.
+#[derive(Clone, Copy)]
+pub struct JobSetup {
+    /// The process group to join, or `None` to stay in the group of the shell.
+    pgid: Option<libc::pid_t>,
+    /// The terminal to take over, for jobs in the foreground.
+    terminal: Option<RawFd>,
+}
+
+impl JobSetup {
+    pub fn apply(self) -> io::Result<()> {
+        if let Some(pgid) = self.pgid {
+            sys::setpgid(0, pgid)?;
+        }
+        if let Some(terminal) = self.terminal {
+            sys::tcsetpgrp(terminal, sys::getpgrp())?;
+        }
+        // Ignored signals stay ignored in the programs we run, unless we reset them.
+        signals::restore_defaults();
+        Ok(())
+    }
+}
+
+/// The terminal the shell controls.
+struct Terminal {
+    fd: OwnedFd,
+    /// The process group of the shell.
+    pgid: libc::pid_t,
+    /// The modes of the terminal the shell uses, restored when a job is stopped
+    /// or killed, so the prompt works even if the job left the terminal in raw mode.
+    modes: libc::termios,
+}
+
+/// The jobs of the shell.
+pub struct JobTable {
+    /// The jobs that run in the background, ordered by id.
+    jobs: Vec<Job>,
+    /// The ids of the jobs, from the least to the most recently used.
+    /// The last one is the current job `%+`, the one before it is `%-`.
+    recent: Vec<usize>,
+    /// The terminal, if the shell controls it.
+    terminal: Option<Terminal>,
+    /// Whether jobs get a process group of their own (`set -m`).
+    /// Like in other shells, this is only on by default when the shell
+    /// controls the terminal, so the jobs of a script stay in its group.
+    /// A background copy of the shell, like in `a && b &`, keeps its commands
+    /// in its own process group, so they are all part of the same job.
+    monitor: bool,
+    /// What the last job that ran in the foreground used, for `time`.
+    pub usage: Usage,
+    /// The pid of the last job started in the background, for `$!`.
+    last_pid: Option<libc::pid_t>,
+}
+
+impl JobTable {
+    /// Create the job table, and take control of the terminal
+    /// if the shell runs in one.
+    pub fn new() -> Self {
+        let terminal = Self::take_terminal();
+        Self {
+            jobs: Vec::new(),
+            recent: Vec::new(),
+            monitor: terminal.is_some(),
+            terminal,
+            usage: Usage::default(),
+            last_pid: None,
+        }
+    }
+
+    fn take_terminal() -> Option<Terminal> {
+        if !io::stdin().is_terminal() {
+            return None;
+        }
+        // Keep our own copy, so redirecting stdin doesn't lose the terminal.
+        let fd = sys::dup_at_least(0, SHELL_FD_BASE).ok()?;
+        for &signal in IGNORED_SIGNALS {
+            signals::ignore(signal);
+        }
+        // This fails if the shell already leads its session, which is fine.
+        let _ = sys::setpgid(0, 0);
+        let pgid = sys::getpgrp();
+        sys::tcsetpgrp(fd.as_raw_fd(), pgid).ok()?;
+        let modes = sys::tcgetattr(fd.as_raw_fd()).ok()?;
+        Some(Terminal { fd, pgid, modes })
+    }
+
+    /// Whether the shell runs in a terminal, where it reports on background jobs.
+    pub fn interactive(&self) -> bool {
+        self.terminal.is_some()
+    }
+
+    pub fn monitor(&self) -> bool {
+        self.monitor
+    }
+
+    /// Turn job control on or off, like `set -m` and `set +m`.
+    pub fn set_monitor(&mut self, monitor: bool) {
+        self.monitor = monitor;
+    }
+
+    /// Forget all jobs and stop using job control, in a copy of the shell
+    /// that runs in the background.
+    pub fn enter_subshell(&mut self) {
+        self.jobs.clear();
+        self.recent.clear();
+        self.terminal = None;
+        self.monitor = false;
+    }
+
+    /// How the next process of `job` joins it.
+    pub fn setup(&self, job: &Job, foreground: bool) -> JobSetup {
+        JobSetup {
+            pgid: self.monitor.then_some(job.pgid),
+            terminal: self
+                .terminal
+                .as_ref()
+                .filter(|_| foreground && self.monitor)
+                .map(|terminal| terminal.fd.as_raw_fd()),
+        }
+    }
+
+    /// Wait for `job` in the foreground, and return its status and the
+    /// status of each of its commands.
+    ///
+    /// If the job is stopped, like with Ctrl-Z, it goes to the job table
+    /// so `fg` or `bg` can continue it later.
+    pub fn run_foreground(&mut self, mut job: Job) -> (ExitStatus, Vec<ExitStatus>) {
+        self.give_terminal(&job);
+        job.wait();
+        self.take_terminal_back(&mut job);
+        self.usage = job.usage;
+
+        let statuses = job.statuses();
+        if let Some(signal) = job.stop_signal().filter(|_| job.is_stopped()) {
+            let id = self.insert(job);
+            if let Some(job) = self.jobs.iter().find(|job| job.id == id) {
+                // Ctrl-Z leaves the cursor after `^Z`.
+                eprintln!("\n{}", self.describe(job));
+            }
+            return (stopped_status(signal), statuses);
+        }
+        let status = job.status().unwrap_or(ExitStatus::from_raw(0));
+        match status.signal() {
+            // Ctrl-C leaves the cursor after `^C`.
+            Some(libc::SIGINT) if self.interactive() => eprintln!(),
+            // Like in other shells, a command that stops reading its input
+            // or is interrupted isn't worth a message.
+            Some(libc::SIGINT | libc::SIGPIPE) | None => {}
+            Some(signal) => eprintln!("{}", describe_signal(signal, status.core_dumped())),
+        }
+        (status, statuses)
+    }
+
+    /// Let `job` use the terminal, with the modes it had when it was stopped.
+    fn give_terminal(&self, job: &Job) {
+        let Some(terminal) = self.terminal.as_ref().filter(|_| self.monitor) else {
+            return;
+        };
+        // There is nothing sensible to do if these fail.
+        if let Some(modes) = &job.modes {
+            let _ = sys::tcsetattr(terminal.fd.as_raw_fd(), modes);
+        }
+        if job.pgid != 0 {
+            let _ = sys::tcsetpgrp(terminal.fd.as_raw_fd(), job.pgid);
+        }
+    }
+
+    /// Take the terminal back from `job`, and restore the modes of the shell
+    /// unless the job ended normally (`stty` changes the modes on purpose).
+    fn take_terminal_back(&mut self, job: &mut Job) {
+        let monitor = self.monitor;
+        let Some(terminal) = self.terminal.as_mut().filter(|_| monitor) else {
+            return;
+        };
+        let fd = terminal.fd.as_raw_fd();
+        // There is nothing sensible to do if these fail.
+        let _ = sys::tcsetpgrp(fd, terminal.pgid);
+        let modes = sys::tcgetattr(fd).ok();
+        match job.status() {
+            Some(status) if status.signal().is_none() => {
+                if let Some(modes) = modes {
+                    terminal.modes = modes;
+                }
+            }
+            _ => {
+                if job.is_stopped() {
+                    job.modes = modes;
+                }
+                let _ = sys::tcsetattr(fd, &terminal.modes);
+            }
+        }
+    }
+
+    /// Let `job` run in the background, and return its id.
+    pub fn add(&mut self, job: Job) -> usize {
+        let interactive = self.interactive();
+        let pid = match job.processes.last() {
+            Some(Process::Running(pid)) => Some(*pid),
+            _ => None,
+        };
+        let id = self.insert(job);
+        self.last_pid = pid.or(self.last_pid);
+        if let Some(pid) = pid.filter(|_| interactive) {
+            eprintln!("[{id}] {pid}");
+        }
+        id
+    }
+
+    /// Put `job` in the table as the current job, and return its id.
+    ///
+    /// A job that was in the table before keeps its id.
+    fn insert(&mut self, mut job: Job) -> usize {
+        if job.id == 0 {
+            job.id = self.jobs.last().map_or(1, |last| last.id + 1);
+        }
+        let id = job.id;
+        job.changed = false;
+        let index = self.jobs.partition_point(|other| other.id < id);
+        self.jobs.insert(index, job);
+        self.recent.push(id);
+        id
+    }
+
+    /// Find the job that `spec` refers to, like `%2`, `%+`, `%-`, `%vim` or `%?file`.
+    ///
+    /// Without a spec, this is the current job.
+    pub fn find(&self, spec: Option<&str>) -> Result<usize, String> {
+        let Some(spec) = spec else {
+            return self
+                .recent
+                .last()
+                .copied()
+                .ok_or_else(|| "current: no such job".to_string());
+        };
+        let no_such_job = || format!("{spec}: no such job");
+        let name = spec.strip_prefix('%').unwrap_or(spec);
+        let recent = |back: usize| {
+            self.recent
+                .len()
+                .checked_sub(back)
+                .map(|i| self.recent[i])
+                .ok_or_else(no_such_job)
+        };
+        match name {
+            "" | "+" | "%" => return recent(1),
+            "-" => return recent(2),
+            _ => {}
+        }
+        if let Ok(id) = name.parse() {
+            return match self.jobs.iter().any(|job| job.id == id) {
+                true => Ok(id),
+                false => Err(no_such_job()),
+            };
+        }
+        let matches: Vec<_> = match name.strip_prefix('?') {
+            Some(part) => self.ids_where(|job| job.command.contains(part)),
+            None => self.ids_where(|job| job.command.starts_with(name)),
+        };
+        match matches[..] {
+            [id] => Ok(id),
+            [] => Err(no_such_job()),
+            _ => Err(format!("{spec}: ambiguous job spec")),
+        }
+    }
+
+    fn ids_where(&self, predicate: impl Fn(&Job) -> bool) -> Vec<usize> {
+        self.jobs
+            .iter()
+            .filter(|job| predicate(job))
+            .map(|job| job.id)
+            .collect()
+    }
+
+    /// Take the job `id` out of the table.
+    fn remove(&mut self, id: usize) -> Option<Job> {
+        self.recent.retain(|&recent| recent != id);
+        let index = self.jobs.iter().position(|job| job.id == id)?;
+        Some(self.jobs.remove(index))
+    }
+
+    /// Send `signal` to the processes of the job `id`, for `kill %id`.
+    ///
+    /// A stopped job is continued as well, so it doesn't wait for `fg` to
+    /// get the signal.
+    pub fn signal(&mut self, id: usize, signal: libc::c_int) -> Result<(), String> {
+        // A pgid of 0 would be the process group of the shell itself.
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id && job.pgid != 0)
+            .ok_or_else(|| format!("%{id}: no such job"))?;
+        // The job may have been stopped by someone else since it was last checked.
+        job.poll();
+        let error = |e| format!("%{id}: {}", error_message(&e));
+        job.kill(signal, self.monitor).map_err(error)?;
+        if job.is_stopped() && signal != libc::SIGKILL && signal != libc::SIGCONT {
+            job.kill(libc::SIGCONT, self.monitor).map_err(error)?;
+        }
+        Ok(())
+    }
+
+    /// Send `SIGHUP` to the jobs, because the terminal is gone or the shell
+    /// exits with `huponexit`. Stopped jobs are continued first, so they get it.
+    ///
+    /// Jobs marked with `disown -h` are left alone.
+    pub fn hangup(&mut self) {
+        self.poll();
+        for job in &self.jobs {
+            if job.nohup || job.pgid == 0 || job.is_done() {
+                continue;
+            }
+            // There is nothing sensible to do if these fail.
+            if job.is_stopped() {
+                let _ = job.kill(libc::SIGCONT, self.monitor);
+            }
+            let _ = job.kill(libc::SIGHUP, self.monitor);
+        }
+    }
+
+    /// Forget the job `id`, so the shell doesn't report on it or send it
+    /// `SIGHUP`. With `keep`, the job stays in the table and only misses
+    /// the `SIGHUP`, like with `disown -h`.
+    pub fn disown(&mut self, id: usize, keep: bool) -> Result<(), String> {
+        let no_such_job = || format!("%{id}: no such job");
+        match keep {
+            true => {
+                let job = self.jobs.iter_mut().find(|job| job.id == id);
+                job.ok_or_else(no_such_job)?.nohup = true;
+            }
+            false => {
+                self.remove(id).ok_or_else(no_such_job)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the job with the process `pid`, like `$!`.
+    pub fn find_pid(&self, pid: libc::pid_t) -> Option<usize> {
+        let job = self.jobs.iter().find(|job| job.pids.contains(&pid))?;
+        Some(job.id)
+    }
+
+    /// The pid of the last job started in the background.
+    pub fn last_pid(&self) -> Option<libc::pid_t> {
+        self.last_pid
+    }
+
+    /// Wait until the jobs `ids` have ended, or one of them with `any`,
+    /// and forget the ones that did.
+    ///
+    /// Returns the status of the last job, or of the one that ended with
+    /// `any`. The wait stops early when a signal the shell catches arrives,
+    /// so its trap can run, and the error is that signal.
+    pub fn wait(&mut self, ids: &[usize], any: bool) -> Result<Option<ExitStatus>, libc::c_int> {
+        loop {
+            self.poll();
+            let done: Vec<_> = self
+                .ids_where(Job::is_done)
+                .into_iter()
+                .filter(|id| ids.contains(id))
+                .collect();
+            if (any && !done.is_empty()) || done.len() == ids.len() {
+                let last = match any {
+                    true => done[0],
+                    false => ids.last().copied().unwrap_or_default(),
+                };
+                let mut status = None;
+                for id in done {
+                    let job = self.remove(id);
+                    if id == last {
+                        status = job.and_then(|job| job.status());
+                    }
+                }
+                return Ok(status);
+            }
+            if let Some(signal) = signals::any_pending() {
+                return Err(signal);
+            }
+            match sys::wait_any(libc::WUNTRACED) {
+                Ok((pid, status, used)) => {
+                    for job in &mut self.jobs {
+                        if job.update(pid, status, &used) {
+                            break;
+                        }
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                // The jobs have ended, but someone else waited for them.
+                Err(_) => return Ok(None),
+            }
+        }
+    }
+
+    /// Move the job `id` to the foreground, and wait for it.
+    pub fn foreground(&mut self, id: usize, out: &mut impl Write) -> Result<ExitStatus, String> {
+        if !self.monitor {
+            return Err("no job control".to_string());
+        }
+        let Some(mut job) = self.remove(id) else {
+            return Err(format!("%{id}: no such job"));
+        };
+        job.poll();
+        if job.is_done() {
+            return Err("job has terminated".to_string());
+        }
+        writeln!(out, "{}", job.command).map_err(|e| error_message(&e))?;
+        // The job must have the terminal before it continues, or it would stop again.
+        self.give_terminal(&job);
+        job.resume().map_err(|e| error_message(&e))?;
+        Ok(self.run_foreground(job).0)
+    }
+
+    /// Let the stopped job `id` continue in the background.
+    pub fn background(&mut self, id: usize, out: &mut impl Write) -> Result<(), String> {
+        if !self.monitor {
+            return Err("no job control".to_string());
+        }
+        let Some(mut job) = self.remove(id) else {
+            return Err(format!("%{id}: no such job"));
+        };
+        job.poll();
+        if job.is_done() {
+            return Err("job has terminated".to_string());
+        }
+        if !job.is_stopped() {
+            self.insert(job);
+            return Err(format!("job {id} already in background"));
+        }
+        let resumed = job.resume();
+        let command = job.command.clone();
+        self.insert(job);
+        resumed.map_err(|e| error_message(&e))?;
+        writeln!(out, "[{id}]+ {command} &").map_err(|e| error_message(&e))
+    }
+
+    /// Check which jobs have ended, without waiting for them.
+    pub fn poll(&mut self) {
+        for job in &mut self.jobs {
+            job.poll();
+        }
+    }
+
+    /// Write the state of the jobs `ids` like the `jobs` builtin,
+    /// and forget the jobs that are done.
+    pub fn list(&mut self, ids: &[usize], out: &mut impl Write) -> io::Result<()> {
+        self.poll();
+        for job in &self.jobs {
+            if ids.contains(&job.id) {
+                writeln!(out, "{}", self.describe(job))?;
+            }
+        }
+        self.remove_done(ids);
+        Ok(())
+    }
+
+    /// The ids of all jobs.
+    pub fn ids(&self) -> Vec<usize> {
+        self.jobs.iter().map(|job| job.id).collect()
+    }
+
+    /// The ids of the jobs that are neither stopped nor done.
+    pub fn running_ids(&mut self) -> Vec<usize> {
+        self.poll();
+        self.ids_where(|job| !job.is_done() && !job.is_stopped())
+    }
+
+    /// The ids of the jobs that are stopped.
+    pub fn stopped_ids(&mut self) -> Vec<usize> {
+        self.poll();
+        self.ids_where(Job::is_stopped)
+    }
+
+    /// Tell the user about background jobs that stopped or ended since the last prompt.
+    ///
+    /// A shell that isn't interactive only reaps the jobs that ended. It keeps
+    /// them in the table, so `wait` and `jobs` can still tell how they ended.
+    pub fn notify(&mut self) {
+        self.poll();
+        if !self.interactive() {
+            return;
+        }
+        let changed = self.ids_where(|job| job.changed);
+        for job in self.jobs.iter().filter(|job| changed.contains(&job.id)) {
+            eprintln!("{}", self.describe(job));
+        }
+        for job in &mut self.jobs {
+            job.changed = false;
+        }
+        self.remove_done(&changed);
+    }
+
+    fn remove_done(&mut self, ids: &[usize]) {
+        for id in self.ids_where(Job::is_done) {
+            if ids.contains(&id) {
+                self.remove(id);
+            }
+        }
+    }
+
+    /// Describe a job like `[1]+  Running                 sleep 10 &`.
+    fn describe(&self, job: &Job) -> String {
+        let mark = match self.recent.iter().rev().position(|&id| id == job.id) {
+            Some(0) => '+',
+            Some(1) => '-',
+            _ => ' ',
+        };
+        let background = match job.is_done() || job.is_stopped() {
+            true => "",
+            false => " &",
+        };
+        format!(
+            "[{}]{mark}  {:<24}{}{background}",
+            job.id,
+            job.state(),
+            job.command
+        )
+    }
+}
@@ -0,0 +1,342 @@
+//! Block 6 - Bring your own features!
+//!
+//! This example starts from the block 5 solution and keeps growing it into a
+//! more complete shell. Because it is much bigger than the other blocks, it is
+//! split into modules. Run it with `cargo run --example block6`.
+
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+
+mod builtins;
+mod exec;
+mod expand;
+mod hash;
+mod history_expansion;
+mod jobs;
+mod options;
+mod parser;
+mod redirect;
+mod signals;
+mod sys;
+mod time;
+mod traps;
+mod variables;
+
+use hash::CommandHash;
+use jobs::JobTable;
+use options::Options;
+use parser::chains_from_line;
+use traps::{Event, Traps};
+use variables::Variables;
+
+/// The name of the file in the home directory that is run at startup.
+const RC_FILE: &str = ".rushrc";
+
+/// Alias for our `Result` type. You could also use `anyhow` instead.
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// The state of the shell that lives longer than a single command.
+///
+/// Builtins like `set` need to change this state, so it is passed
+/// to every command that runs.
+pub struct Shell {
+    pub history: builtins::History,
+    pub hash: CommandHash,
+    pub options: Options,
+    pub jobs: JobTable,
+    pub traps: Traps,
+    pub variables: Variables,
+    /// The status of the last command, which `exit` uses by default.
+    pub last_status: i32,
+    /// Set by `exit`: the status to exit with once the current command is done.
+    pub exiting: Option<i32>,
+    /// Whether a trap is running. Traps don't run while another one does.
+    running_trap: bool,
+    /// How many times in a row the input ended, for `IGNOREEOF`.
+    eof_count: usize,
+    /// Whether the user was told about stopped jobs when the input ended.
+    warned_stopped: bool,
+}
+
+impl Shell {
+    fn new() -> Self {
+        let jobs = JobTable::new();
+        Self {
+            history: builtins::History::new(),
+            hash: CommandHash::default(),
+            options: Options {
+                histexpand: jobs.interactive(),
+                history: jobs.interactive(),
+                ..Options::default()
+            },
+            jobs,
+            traps: Traps::default(),
+            variables: Variables::default(),
+            last_status: 0,
+            exiting: None,
+            running_trap: false,
+            eof_count: 0,
+            warned_stopped: false,
+        }
+    }
+
+    /// Run the history expansion on `line`.
+    ///
+    /// Returns the line that should be executed, or `None` if nothing should
+    /// run (the expansion failed or used the `:p` modifier).
+    fn expand_history(&self, line: &str) -> Option<String> {
+        if !self.options.histexpand {
+            return Some(line.to_string());
+        }
+
+        let entries = self.history.entries().unwrap_or_default();
+        match history_expansion::expand(line, &entries) {
+            Ok(expanded) => {
+                if expanded.changed {
+                    // Like bash, show the user what is going to run. A closed
+                    // stdout doesn't stop the shell, so the error is ignored.
+                    let _ = writeln!(io::stdout(), "{}", expanded.line);
+                }
+                if expanded.print_only {
+                    self.add_history(&expanded.line);
+                    return None;
+                }
+                Some(expanded.line)
+            }
+            Err(e) => {
+                eprintln!("rush: {e}");
+                None
+            }
+        }
+    }
+
+    fn add_history(&self, line: &str) {
+        // Like in other shells, a history file that can't be written doesn't
+        // stop the shell, the line is just not remembered.
+        if self.options.history && !line.is_empty() {
+            let _ = self.history.add(line);
+        }
+    }
+
+    /// Run the commands in the rc file, like `set -o noclobber`.
+    ///
+    /// The file is `~/.rushrc`, unless the `RUSHRC` environment variable
+    /// points somewhere else. A missing rc file is not an error.
+    fn source_rc(&mut self) {
+        let path = match std::env::var_os("RUSHRC") {
+            Some(path) => PathBuf::from(path),
+            None => match std::env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(RC_FILE),
+                None => return,
+            },
+        };
+        let Ok(rc) = std::fs::read_to_string(path) else {
+            return;
+        };
+        self.run_script(&rc);
+    }
+
+    /// Run the lines of `script` one after the other, until one of them exits.
+    pub fn run_script(&mut self, script: &str) {
+        for line in script.lines() {
+            if self.exiting.is_some() {
+                break;
+            }
+            if self.options.verbose {
+                eprintln!("{line}");
+            }
+            self.run_line(line);
+        }
+    }
+
+    /// Parse `line` and run all of its commands.
+    fn run_line(&mut self, line: &str) {
+        let chains = match chains_from_line(line) {
+            Ok(chains) => chains,
+            Err(e) => {
+                eprintln!("rush: {e}");
+                return;
+            }
+        };
+        // `set -n` checks a script for syntax errors without running it.
+        if self.options.noexec && !self.jobs.interactive() {
+            return;
+        }
+        for chain in chains {
+            if self.exiting.is_some() {
+                break;
+            }
+            chain.run(self);
+        }
+    }
+
+    /// Run the trap for `event`, if there is one.
+    ///
+    /// The trap doesn't change the status of the last command.
+    pub fn run_trap(&mut self, event: Event) {
+        if self.running_trap {
+            return;
+        }
+        let Some(command) = self.traps.get(event).map(String::from) else {
+            return;
+        };
+        let status = self.last_status;
+        self.running_trap = true;
+        self.run_line(&command);
+        self.running_trap = false;
+        self.last_status = status;
+    }
+
+    /// Run the traps of the signals that arrived since the last check.
+    ///
+    /// Without a trap, `SIGHUP` means the terminal is gone: the shell passes
+    /// it on to its jobs and exits.
+    pub fn run_signal_traps(&mut self) {
+        if self.running_trap {
+            return;
+        }
+        for signal in signals::take_pending() {
+            let event = Event::Signal(signal);
+            if signal == libc::SIGHUP && self.traps.get(event).is_none() {
+                self.jobs.hangup();
+                self.exiting.get_or_insert(128 + signal);
+            }
+            self.run_trap(event);
+        }
+    }
+
+    /// Read the next line from `stdin`, or `None` once the input is exhausted.
+    ///
+    /// The line is read one byte at a time, so the input that follows is left
+    /// for the commands we run, like in `echo hi | rush` running `cat`.
+    /// Traps run while the shell waits, and Ctrl-C makes this fail with `Interrupted`.
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = Vec::new();
+        let mut byte = [0];
+        loop {
+            self.run_signal_traps();
+            if self.exiting.is_some() {
+                return Ok(None);
+            }
+            match sys::read(0, &mut byte) {
+                Ok(0) => break,
+                Ok(_) => {
+                    line.push(byte[0]);
+                    if byte[0] == b'\n' {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                    if signals::is_pending(libc::SIGINT) {
+                        return Err(e);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok((!line.is_empty()).then(|| String::from_utf8_lossy(&line).into_owned()))
+    }
+
+    /// Check whether the shell should exit now that the input ended.
+    ///
+    /// Interactively, Ctrl-D is ignored as many times in a row as `IGNOREEOF`
+    /// says (10 if it isn't a number), and the first one warns about stopped
+    /// jobs instead of leaving them behind, like bash does.
+    fn exits_at_eof(&mut self) -> bool {
+        if !self.jobs.interactive() {
+            return true;
+        }
+        self.eof_count += 1;
+        if let Some(value) = self.variables.get("IGNOREEOF") {
+            if self.eof_count <= value.parse().unwrap_or(10) {
+                eprintln!("\nUse \"exit\" to leave the shell.");
+                return false;
+            }
+        }
+        if !self.warned_stopped && !self.jobs.stopped_ids().is_empty() {
+            self.warned_stopped = true;
+            eprintln!("\nThere are stopped jobs.");
+            return false;
+        }
+        true
+    }
+
+    /// Exit the shell, after running the `EXIT` trap.
+    fn exit(&mut self) -> ! {
+        let status = self.exiting.take().unwrap_or(self.last_status);
+        if let Some(command) = self.traps.get(Event::Exit).map(String::from) {
+            self.traps.set(Event::Exit, None);
+            self.last_status = status;
+            self.run_line(&command);
+        }
+        // `exit` in the trap changes the status, like in other shells.
+        let status = self.exiting.unwrap_or(status);
+        if self.options.huponexit && self.jobs.interactive() {
+            self.jobs.hangup();
+        }
+        let _ = io::stdout().flush();
+        std::process::exit(status);
+    }
+}
+
+fn main() {
+    let mut shell = Shell::new();
+    if shell.jobs.interactive() {
+        // Ctrl-C discards the line being typed instead of killing the shell,
+        // and Ctrl-\ does nothing. Jobs in the foreground still get both.
+        signals::catch(libc::SIGINT);
+        signals::ignore(libc::SIGQUIT);
+        // The jobs get `SIGHUP` too when the terminal goes away.
+        signals::catch(libc::SIGHUP);
+    }
+    // An inherited `$PWD` is kept only if it is still where the shell is.
+    std::env::set_var("PWD", builtins::current_dir());
+    shell.source_rc();
+    while shell.exiting.is_none() {
+        shell.jobs.notify();
+        show_prompt();
+        let line = match shell.read_line() {
+            Ok(Some(line)) => {
+                shell.eof_count = 0;
+                shell.warned_stopped = false;
+                line
+            }
+            // End of input (Ctrl-D or a closed pipe).
+            Ok(None) if shell.exiting.is_some() || shell.exits_at_eof() => break,
+            Ok(None) => continue,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                // The terminal already dropped what was typed, start over on a
+                // new line. A trap for `SIGINT` runs right after this, and `$?`
+                // says the line was interrupted, like in bash.
+                let _ = writeln!(io::stdout());
+                shell.last_status = 128 + libc::SIGINT;
+                continue;
+            }
+            Err(e) => {
+                eprintln!("rush: {}", redirect::error_message(&e));
+                break;
+            }
+        };
+        if shell.options.verbose {
+            eprint!("{line}");
+        }
+        let Some(line) = shell.expand_history(line.trim()) else {
+            continue;
+        };
+        shell.add_history(&line);
+        shell.run_line(&line);
+    }
+    shell.exit();
+}
+
+/// If `stdout` is printed to a terminal, print a prompt.
+/// Otherwise, do nothing. This allows to redirect the shell `stdout`
+/// to a file or another process, without the prompt being printed.
+fn show_prompt() {
+    let mut stdout = std::io::stdout();
+    if stdout.is_terminal() {
+        write!(stdout, "> ").unwrap();
+        // Flush stdout to ensure the prompt is displayed.
+        stdout.flush().expect("can't flush stdout");
+    }
+}
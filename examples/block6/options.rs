@@ -0,0 +1,129 @@
+//! Options that change how the shell behaves.
+
+/// Shell options that can be toggled with the `set` and `shopt` builtins.
+#[derive(Default)]
+pub struct Options {
+    /// Exit as soon as a command fails (`set -e`).
+    pub errexit: bool,
+    /// Expand `!`-style history references (`set -H`).
+    /// Like in bash, only interactive shells have it on by default.
+    pub histexpand: bool,
+    /// Record the lines that are read in the history file (`set -o history`).
+    /// Like in bash, only interactive shells have it on by default.
+    pub history: bool,
+    /// Don't let `>` overwrite existing files (`set -C`). `>|` still does.
+    pub noclobber: bool,
+    /// Read commands without running them, to check a script for syntax
+    /// errors (`set -n`). Interactive shells ignore it.
+    pub noexec: bool,
+    /// Don't expand patterns like `*.txt` (`set -f`).
+    pub noglob: bool,
+    /// Make it an error to use a variable that isn't set (`set -u`).
+    pub nounset: bool,
+    /// Make a pipeline fail if any of its commands fails, instead of only
+    /// the last one (`set -o pipefail`).
+    pub pipefail: bool,
+    /// Print each line of input as it is read (`set -v`).
+    pub verbose: bool,
+    /// Print each command before it runs, after `PS4` (`set -x`).
+    pub xtrace: bool,
+    /// Let patterns match file names that start with a `.` (`shopt -s dotglob`).
+    pub dotglob: bool,
+    /// Send `SIGHUP` to the jobs when an interactive shell exits
+    /// (`shopt -s huponexit`), like it does when the terminal goes away.
+    pub huponexit: bool,
+    /// Expand a pattern that matches nothing to nothing, instead of leaving
+    /// it as it is (`shopt -s nullglob`).
+    pub nullglob: bool,
+}
+
+impl Options {
+    /// The long names accepted by `set -o`, in the order `set -o` lists them.
+    pub const NAMES: &'static [&'static str] = &[
+        "errexit",
+        "histexpand",
+        "history",
+        "noclobber",
+        "noexec",
+        "noglob",
+        "nounset",
+        "pipefail",
+        "verbose",
+        "xtrace",
+    ];
+
+    /// The names of the extended options, which only `shopt` changes.
+    pub const SHOPT_NAMES: &'static [&'static str] = &["dotglob", "huponexit", "nullglob"];
+
+    /// The single-letter flags of the options, in the order `$-` shows them.
+    const FLAGS: &'static [(char, &'static str)] = &[
+        ('e', "errexit"),
+        ('f', "noglob"),
+        ('n', "noexec"),
+        ('u', "nounset"),
+        ('v', "verbose"),
+        ('x', "xtrace"),
+        ('C', "noclobber"),
+        ('H', "histexpand"),
+    ];
+
+    /// Get the option called `name`, as used by `set -o name` or `shopt name`.
+    pub fn get(&self, name: &str) -> Option<bool> {
+        match name {
+            "errexit" => Some(self.errexit),
+            "histexpand" => Some(self.histexpand),
+            "history" => Some(self.history),
+            "noclobber" => Some(self.noclobber),
+            "noexec" => Some(self.noexec),
+            "noglob" => Some(self.noglob),
+            "nounset" => Some(self.nounset),
+            "pipefail" => Some(self.pipefail),
+            "verbose" => Some(self.verbose),
+            "xtrace" => Some(self.xtrace),
+            "dotglob" => Some(self.dotglob),
+            "huponexit" => Some(self.huponexit),
+            "nullglob" => Some(self.nullglob),
+            _ => None,
+        }
+    }
+
+    /// Turn the option called `name` on or off.
+    ///
+    /// Returns `false` if there is no such option.
+    pub fn set(&mut self, name: &str, value: bool) -> bool {
+        match name {
+            "errexit" => self.errexit = value,
+            "histexpand" => self.histexpand = value,
+            "history" => self.history = value,
+            "noclobber" => self.noclobber = value,
+            "noexec" => self.noexec = value,
+            "noglob" => self.noglob = value,
+            "nounset" => self.nounset = value,
+            "pipefail" => self.pipefail = value,
+            "verbose" => self.verbose = value,
+            "xtrace" => self.xtrace = value,
+            "dotglob" => self.dotglob = value,
+            "huponexit" => self.huponexit = value,
+            "nullglob" => self.nullglob = value,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Map a single-letter flag like the `H` in `set -H` to its long name.
+    pub fn name_of_flag(flag: char) -> Option<&'static str> {
+        Self::FLAGS
+            .iter()
+            .find(|(known, _)| *known == flag)
+            .map(|(_, name)| *name)
+    }
+
+    /// The flags of the options that are on, like `eH`, as `$-` shows them.
+    pub fn flags(&self) -> String {
+        Self::FLAGS
+            .iter()
+            .filter(|(_, name)| self.get(name).unwrap_or_default())
+            .map(|(flag, _)| flag)
+            .collect()
+    }
+}
@@ -0,0 +1,790 @@
+//! Turn a line of input into [`Chain`]s of commands.
+
+use crate::Result;
+
+// This struct doesn't use lifetimes to keep the code simple.
+// You can try to use `&str` instead of `String`
+// to avoid unnecessary allocations. 👍
+#[derive(PartialEq, Debug)]
+pub struct Cmd {
+    pub binary: String,
+    pub args: Vec<String>,
+    /// Redirections, in the order they appear on the command line.
+    pub redirects: Vec<Redirect>,
+}
+
+/// A redirection like `> out.txt` or `2>&1`.
+#[derive(PartialEq, Debug)]
+pub struct Redirect {
+    /// The file descriptor that is redirected, like the `2` in `2> errors.txt`.
+    pub fd: i32,
+    pub kind: RedirectKind,
+    /// The file to redirect to or from.
+    /// For [`RedirectKind::Duplicate`], the file descriptor to copy, or `-` to close `fd`.
+    pub target: String,
+    /// The variable written in front of the operator, like `fd` in `{fd}<file`.
+    /// The shell picks a free file descriptor then, which stays open after
+    /// the command (see [`crate::redirect::open_persistent`]).
+    pub var: Option<String>,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum RedirectKind {
+    /// `<`
+    Read,
+    /// `>`
+    Write,
+    /// `>>`
+    Append,
+    /// `>|`, which overwrites files even with `noclobber`
+    Clobber,
+    /// `<>`
+    ReadWrite,
+    /// `>&` and `<&`
+    Duplicate,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum Element {
+    /// `|`
+    Pipe,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+    /// Command.
+    Cmd(Cmd),
+}
+
+/// A token of the command line.
+#[derive(PartialEq, Debug)]
+enum Token {
+    /// A word, with quotes already removed. The characters that were quoted
+    /// are marked for the expansion (see [`push_literal`]).
+    Word(String),
+    /// `|`, `&&`, `||`, `;` or `&`.
+    Operator(&'static str),
+    /// A redirection operator like `<` or `>>`, with the file descriptor
+    /// written in front of it (like the `2` in `2>`), or the variable
+    /// (like the `fd` in `{fd}>`), if any.
+    Redirect {
+        fd: Option<i32>,
+        var: Option<String>,
+        operator: &'static str,
+    },
+}
+
+/// Parse `[Element]`s from a string.
+struct Parser {
+    current: usize,
+    tokens: Vec<Token>,
+}
+
+impl Parser {
+    fn new(line: &str) -> Result<Self> {
+        Ok(Self {
+            tokens: tokenize(line)?,
+            current: 0,
+        })
+    }
+
+    /// Parse all chains of the line, which are separated by `;` or `&`.
+    fn parse(mut self) -> Result<Vec<Chain>> {
+        let mut chains = vec![];
+        while self.current < self.tokens.len() {
+            if let Some(chain) = self.parse_chain()? {
+                chains.push(chain);
+            }
+        }
+        Ok(chains)
+    }
+
+    fn parse_chain(&mut self) -> Result<Option<Chain>> {
+        let mut elements = vec![];
+        while let Some(e) = self.parse_next()? {
+            elements.push(e);
+        }
+        // `parse_next` stops right after the `;` or `&` that ends the chain.
+        let background = self.current > 0 && self.tokens[self.current - 1] == Token::Operator("&");
+        if background && elements.is_empty() {
+            return Err(unexpected("&"));
+        }
+        if !elements.is_empty() {
+            Ok(Some(Chain {
+                elements,
+                background,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_next(&mut self) -> Result<Option<Element>> {
+        match self.tokens.get(self.current) {
+            None => Ok(None),
+            Some(Token::Operator(";" | "&")) => {
+                self.current += 1;
+                Ok(None)
+            }
+            Some(Token::Operator(operator)) => {
+                let operator = Element::parse_operator(operator);
+                self.current += 1;
+                Ok(operator)
+            }
+            Some(_) => self.parse_cmd().map(|cmd| Some(Element::Cmd(cmd))),
+        }
+    }
+
+    /// Parse the words and redirections of a command, up to the next operator.
+    ///
+    /// Redirections can appear anywhere, even before the binary: `> out echo hi`.
+    fn parse_cmd(&mut self) -> Result<Cmd> {
+        let mut words: Vec<String> = vec![];
+        let mut redirects = vec![];
+        loop {
+            match self.tokens.get(self.current) {
+                // found operator, so I already parsed all cmd
+                Some(Token::Operator(_)) | None => break,
+                Some(Token::Word(word)) => words.push(word.to_string()),
+                Some(Token::Redirect { fd, var, operator }) => {
+                    let (fd, var, operator) = (*fd, var.clone(), *operator);
+                    self.current += 1;
+                    let target = match self.tokens.get(self.current) {
+                        Some(Token::Word(target)) => target.to_string(),
+                        Some(token) => return Err(unexpected(token.to_string())),
+                        None => return Err(unexpected("newline")),
+                    };
+                    redirects.extend(Redirect::parse(fd, var, operator, target));
+                }
+            }
+            self.current += 1;
+        }
+
+        // A command can consist of redirections only, like `> out.txt`.
+        // It has an empty binary then.
+        let mut words = words.into_iter();
+        Ok(Cmd {
+            binary: words.next().unwrap_or_default(),
+            args: words.collect(),
+            redirects,
+        })
+    }
+}
+
+impl Redirect {
+    /// Build the redirections for an operator like `2>` and its target.
+    ///
+    /// `&>file` (and `>&file` when `file` isn't a number) is short for
+    /// `>file 2>&1`, so it turns into two redirections.
+    fn parse(fd: Option<i32>, var: Option<String>, operator: &str, target: String) -> Vec<Self> {
+        let (fd, kind) = match operator {
+            "<" => (fd.unwrap_or(0), RedirectKind::Read),
+            ">" => (fd.unwrap_or(1), RedirectKind::Write),
+            ">>" => (fd.unwrap_or(1), RedirectKind::Append),
+            ">|" => (fd.unwrap_or(1), RedirectKind::Clobber),
+            "<>" => (fd.unwrap_or(0), RedirectKind::ReadWrite),
+            "<&" => (fd.unwrap_or(0), RedirectKind::Duplicate),
+            // `>&$fd` is only known to be a number once it's expanded.
+            ">&" if fd.is_some()
+                || var.is_some()
+                || target == "-"
+                || target.starts_with('$')
+                || target.parse::<i32>().is_ok() =>
+            {
+                (fd.unwrap_or(1), RedirectKind::Duplicate)
+            }
+            // `&>`, `&>>` and `>&file`.
+            _ => {
+                let kind = match operator {
+                    "&>>" => RedirectKind::Append,
+                    _ => RedirectKind::Write,
+                };
+                return vec![
+                    Self {
+                        fd: 1,
+                        kind,
+                        target,
+                        var: None,
+                    },
+                    Self {
+                        fd: 2,
+                        kind: RedirectKind::Duplicate,
+                        target: "1".to_string(),
+                        var: None,
+                    },
+                ];
+            }
+        };
+        vec![Self {
+            fd,
+            kind,
+            target,
+            var,
+        }]
+    }
+}
+
+impl Token {
+    /// A redirection operator without a file descriptor in front of it.
+    fn redirect(operator: &'static str) -> Self {
+        Token::Redirect {
+            fd: None,
+            var: None,
+            operator,
+        }
+    }
+}
+
+/// Check whether `name` is a valid variable name, like `fd` or `_out2`.
+pub fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Check whether `name` can hold the file descriptor of a redirection like
+/// `{fd}>file`: a variable, or an element of an array like `COPROC[1]`.
+fn is_fd_variable(name: &str) -> bool {
+    match name.strip_suffix(']').and_then(|name| name.split_once('[')) {
+        Some((name, index)) => {
+            is_name(name) && !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())
+        }
+        None => is_name(name),
+    }
+}
+
+fn unexpected(token: impl std::fmt::Display) -> Box<dyn std::error::Error> {
+    format!("syntax error near unexpected token `{token}'").into()
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{word}"),
+            Token::Operator(operator) => write!(f, "{operator}"),
+            Token::Redirect { fd, var, operator } => {
+                if let Some(fd) = fd {
+                    write!(f, "{fd}")?;
+                }
+                if let Some(var) = var {
+                    write!(f, "{{{var}}}")?;
+                }
+                write!(f, "{operator}")
+            }
+        }
+    }
+}
+
+/// Split a line into [`Token`]s.
+///
+/// Words are separated by whitespace or operators, and a `#` at the start of
+/// a word starts a comment. Like in other shells,
+/// `'...'` quotes everything literally, while inside `"..."` a backslash
+/// can escape `"`, `\`, `$` and `` ` ``. Outside quotes, a backslash
+/// escapes any character.
+fn tokenize(line: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+    // The word that is being read, if any. An empty quoted string like `''`
+    // is still a word, so we can't use an empty `String` to mean "no word".
+    let mut word: Option<String> = None;
+    // Whether the current word contains quotes or backslashes.
+    // `2>` redirects stderr, but `'2'>` passes `2` as an argument.
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        let operator = match c {
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Operator("||"),
+            '|' => Token::Operator("|"),
+            '&' if chars.next_if_eq(&'&').is_some() => Token::Operator("&&"),
+            '&' if chars.next_if_eq(&'>').is_some() => match chars.next_if_eq(&'>') {
+                Some(_) => Token::redirect("&>>"),
+                None => Token::redirect("&>"),
+            },
+            '&' => Token::Operator("&"),
+            ';' => Token::Operator(";"),
+            '<' | '>' => {
+                let fd = match &word {
+                    Some(digits) if !quoted && digits.bytes().all(|b| b.is_ascii_digit()) => {
+                        digits.parse().ok()
+                    }
+                    _ => None,
+                };
+                let var = match &word {
+                    Some(word) if !quoted => word
+                        .strip_prefix('{')
+                        .and_then(|word| word.strip_suffix('}'))
+                        .filter(|name| is_fd_variable(name))
+                        .map(String::from),
+                    _ => None,
+                };
+                if fd.is_some() || var.is_some() {
+                    word = None;
+                }
+                let operator = match (c, chars.peek()) {
+                    ('<', Some('>')) => "<>",
+                    ('<', Some('&')) => "<&",
+                    ('>', Some('>')) => ">>",
+                    ('>', Some('&')) => ">&",
+                    ('>', Some('|')) => ">|",
+                    ('<', _) => "<",
+                    _ => ">",
+                };
+                if operator.len() == 2 {
+                    chars.next();
+                }
+                Token::Redirect { fd, var, operator }
+            }
+            // A comment runs to the end of the line.
+            '#' if word.is_none() => break,
+            c if c.is_whitespace() => {
+                tokens.extend(word.take().map(Token::Word));
+                quoted = false;
+                continue;
+            }
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                if let Some(c) = chars.next() {
+                    push_literal(word, c);
+                }
+                quoted = true;
+                continue;
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => push_literal(word, c),
+                        None => return Err("unexpected EOF while looking for matching `''".into()),
+                    }
+                }
+                quoted = true;
+                continue;
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            match chars.next_if(|c| matches!(c, '"' | '\\' | '$' | '`')) {
+                                Some(escaped) => push_literal(word, escaped),
+                                None => push_literal(word, '\\'),
+                            }
+                        }
+                        // Parameters are expanded in double quotes, but patterns
+                        // aren't. The name of the parameter is kept as it is,
+                        // like the `?` in `"$?"` or the `[` in `"${a[0]}"`.
+                        Some('$') => {
+                            word.push('$');
+                            match chars.next_if(|c| matches!(c, '?' | '{')) {
+                                Some('{') => {
+                                    word.push('{');
+                                    while let Some(c) = chars.next_if(|c| *c != '"') {
+                                        word.push(c);
+                                        if c == '}' {
+                                            break;
+                                        }
+                                    }
+                                }
+                                Some(c) => word.push(c),
+                                None => {}
+                            }
+                        }
+                        Some(c) => push_literal(word, c),
+                        None => return Err("unexpected EOF while looking for matching `\"'".into()),
+                    }
+                }
+                quoted = true;
+                continue;
+            }
+            c => {
+                word.get_or_insert_with(String::new).push(c);
+                continue;
+            }
+        };
+        tokens.extend(word.take().map(Token::Word));
+        quoted = false;
+        tokens.push(operator);
+    }
+    tokens.extend(word.take().map(Token::Word));
+    Ok(tokens)
+}
+
+/// Add `c` to `word` as a literal character, one that was quoted or escaped.
+///
+/// Parameters and patterns are only expanded right before a command runs
+/// (see [`crate::expand`]). Until then, the characters that would be special
+/// to the expansion are kept escaped with a backslash, so `'$HOME'` and `\*`
+/// stay as they are.
+pub fn push_literal(word: &mut String, c: char) {
+    if matches!(c, '\\' | '$' | '*' | '?' | '[') {
+        word.push('\\');
+    }
+    word.push(c);
+}
+
+/// Remove the backslashes that [`push_literal`] added.
+pub fn unescape(word: &str) -> String {
+    let mut unescaped = String::with_capacity(word.len());
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[derive(PartialEq, Debug)]
+pub struct Chain {
+    pub elements: Vec<Element>,
+    /// Whether the chain ends with `&`, to run it in the background.
+    pub background: bool,
+}
+
+// Commands are displayed for `jobs`, the way they would be typed.
+// Quotes are not added back, so this is not always valid input.
+
+impl std::fmt::Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, element) in self.elements.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            match element {
+                Element::Pipe => write!(f, "|")?,
+                Element::And => write!(f, "&&")?,
+                Element::Or => write!(f, "||")?,
+                Element::Cmd(cmd) => write!(f, "{cmd}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Cmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let words = std::iter::once(&self.binary)
+            .filter(|binary| !binary.is_empty())
+            .chain(&self.args);
+        for (i, word) in words.enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", unescape(word))?;
+        }
+        for (i, redirect) in self.redirects.iter().enumerate() {
+            if i > 0 || !self.binary.is_empty() {
+                write!(f, " ")?;
+            }
+            write!(f, "{redirect}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Redirect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (default_fd, operator) = match self.kind {
+            RedirectKind::Read => (0, "<"),
+            RedirectKind::Write => (1, ">"),
+            RedirectKind::Append => (1, ">>"),
+            RedirectKind::Clobber => (1, ">|"),
+            RedirectKind::ReadWrite => (0, "<>"),
+            RedirectKind::Duplicate if self.fd == 0 => (0, "<&"),
+            RedirectKind::Duplicate => (1, ">&"),
+        };
+        if let Some(var) = &self.var {
+            write!(f, "{{{var}}}")?;
+        } else if self.fd != default_fd {
+            write!(f, "{}", self.fd)?;
+        }
+        write!(f, "{operator}{}", unescape(&self.target))
+    }
+}
+
+impl Element {
+    fn parse_operator(token: &str) -> Option<Self> {
+        match token {
+            "|" => Some(Self::Pipe),
+            "&&" => Some(Self::And),
+            "||" => Some(Self::Or),
+            _ => None,
+        }
+    }
+}
+
+/// Parse all chains of commands of a line.
+pub fn chains_from_line(line: &str) -> Result<Vec<Chain>> {
+    Parser::new(line)?.parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_chains(line: &str) -> Vec<Chain> {
+        chains_from_line(line).unwrap()
+    }
+
+    #[test]
+    fn no_cmd_is_parsed_from_empty_line() {
+        assert_eq!(parse_chains(""), vec![]);
+    }
+
+    #[test]
+    fn cmd_with_no_args_is_parsed() {
+        assert_eq!(
+            parse_chains("ls"),
+            vec![Chain {
+                elements: vec![Element::Cmd(Cmd {
+                    binary: "ls".to_string(),
+                    args: vec![],
+                    redirects: vec![]
+                }),],
+                background: false,
+            },]
+        );
+    }
+
+    #[test]
+    fn cmd_with_args_is_parsed() {
+        assert_eq!(
+            parse_chains("ls -l"),
+            vec![Chain {
+                elements: vec![Element::Cmd(Cmd {
+                    binary: "ls".to_string(),
+                    args: vec!["-l".to_string()],
+                    redirects: vec![]
+                })],
+                background: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn cmds_are_parsed() {
+        assert_eq!(
+            parse_chains("ls; echo hello"),
+            vec![
+                Chain {
+                    elements: vec![Element::Cmd(Cmd {
+                        binary: "ls".to_string(),
+                        args: vec![],
+                        redirects: vec![]
+                    }),],
+                    background: false,
+                },
+                Chain {
+                    elements: vec![Element::Cmd(Cmd {
+                        binary: "echo".to_string(),
+                        args: vec!["hello".to_string()],
+                        redirects: vec![]
+                    }),],
+                    background: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn pipe_is_parsed() {
+        assert_eq!(
+            parse_chains("ls | wc -l"),
+            vec![Chain {
+                elements: vec![
+                    Element::Cmd(Cmd {
+                        binary: "ls".to_string(),
+                        args: vec![],
+                        redirects: vec![]
+                    }),
+                    Element::Pipe,
+                    Element::Cmd(Cmd {
+                        binary: "wc".to_string(),
+                        args: vec!["-l".to_string()],
+                        redirects: vec![]
+                    }),
+                ],
+                background: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn redirections_are_parsed_anywhere() {
+        assert_eq!(
+            parse_chains("> out echo hi >>log <in"),
+            vec![Chain {
+                elements: vec![Element::Cmd(Cmd {
+                    binary: "echo".to_string(),
+                    args: vec!["hi".to_string()],
+                    redirects: vec![
+                        Redirect {
+                            fd: 1,
+                            kind: RedirectKind::Write,
+                            target: "out".to_string(),
+                            var: None,
+                        },
+                        Redirect {
+                            fd: 1,
+                            kind: RedirectKind::Append,
+                            target: "log".to_string(),
+                            var: None,
+                        },
+                        Redirect {
+                            fd: 0,
+                            kind: RedirectKind::Read,
+                            target: "in".to_string(),
+                            var: None,
+                        },
+                    ]
+                })],
+                background: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn quotes_are_removed() {
+        assert_eq!(
+            parse_chains(r#"echo 'a;b' "c \"d\"" e\ f '' ">""#),
+            vec![Chain {
+                elements: vec![Element::Cmd(Cmd {
+                    binary: "echo".to_string(),
+                    args: vec![
+                        "a;b".to_string(),
+                        "c \"d\"".to_string(),
+                        "e f".to_string(),
+                        "".to_string(),
+                        ">".to_string()
+                    ],
+                    redirects: vec![]
+                })],
+                background: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn quoted_special_characters_are_marked() {
+        let chains = parse_chains(r#"echo '$a' "$b $? ${c[0]} *" \? *"#);
+        let Element::Cmd(cmd) = &chains[0].elements[0] else {
+            panic!("expected a command");
+        };
+        assert_eq!(cmd.args, [r"\$a", r"$b $? ${c[0]} \*", r"\?", "*"]);
+        assert_eq!(cmd.to_string(), "echo $a $b $? ${c[0]} * ? *");
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        let error = |line: &str| chains_from_line(line).unwrap_err().to_string();
+        assert_eq!(
+            error("echo >"),
+            "syntax error near unexpected token `newline'"
+        );
+        assert_eq!(
+            error("echo > | cat"),
+            "syntax error near unexpected token `|'"
+        );
+        assert_eq!(
+            error("echo 'oops"),
+            "unexpected EOF while looking for matching `''"
+        );
+    }
+
+    fn redirects(line: &str) -> Vec<(i32, RedirectKind, String)> {
+        let mut chains = parse_chains(line);
+        let Element::Cmd(cmd) = chains.remove(0).elements.remove(0) else {
+            panic!("expected a command");
+        };
+        cmd.redirects
+            .into_iter()
+            .map(|r| (r.fd, r.kind, r.target))
+            .collect()
+    }
+
+    #[test]
+    fn fd_redirections_are_parsed() {
+        use RedirectKind::*;
+        assert_eq!(
+            redirects("cmd 2>err 2>>log >|force 9<>rw 3<&0 2>&1 >&2 4>&- 5 > five '6'>six"),
+            vec![
+                (2, Write, "err".to_string()),
+                (2, Append, "log".to_string()),
+                (1, Clobber, "force".to_string()),
+                (9, ReadWrite, "rw".to_string()),
+                (3, Duplicate, "0".to_string()),
+                (2, Duplicate, "1".to_string()),
+                (1, Duplicate, "2".to_string()),
+                (4, Duplicate, "-".to_string()),
+                (1, Write, "five".to_string()),
+                (1, Write, "six".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn redirections_to_variables_are_parsed() {
+        let mut chains =
+            parse_chains("exec {fd}<in {out}>&1 {A[1]}>&- '{x}'>file {1x}>file {A[x]}>file");
+        let Element::Cmd(cmd) = chains.remove(0).elements.remove(0) else {
+            panic!("expected a command");
+        };
+        let vars: Vec<_> = cmd.redirects.iter().map(|r| r.var.as_deref()).collect();
+        assert_eq!(
+            vars,
+            [Some("fd"), Some("out"), Some("A[1]"), None, None, None]
+        );
+        assert_eq!(cmd.args, ["{x}", "{1x}", "{A[x]}"]);
+        assert_eq!(cmd.redirects[1].kind, RedirectKind::Duplicate);
+        assert_eq!(
+            cmd.to_string(),
+            "exec {x} {1x} {A[x]} {fd}<in {out}>&1 {A[1]}>&- >file >file >file"
+        );
+    }
+
+    #[test]
+    fn redirections_of_both_outputs_are_parsed() {
+        use RedirectKind::*;
+        let both = |kind| {
+            vec![
+                (1, kind, "out".to_string()),
+                (2, Duplicate, "1".to_string()),
+            ]
+        };
+        assert_eq!(redirects("cmd &>out"), both(Write));
+        assert_eq!(redirects("cmd &>> out"), both(Append));
+        assert_eq!(redirects("cmd >&out"), both(Write));
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        assert_eq!(parse_chains("# just a comment"), vec![]);
+        assert_eq!(parse_chains("echo a#b # c"), parse_chains("echo a#b"));
+    }
+
+    #[test]
+    fn background_chains_are_parsed() {
+        let chains = parse_chains("sleep 1 & echo a && echo b &");
+        assert_eq!(
+            chains
+                .iter()
+                .map(|chain| chain.background)
+                .collect::<Vec<_>>(),
+            vec![true, true]
+        );
+        assert!(!parse_chains("ls & ls")[1].background);
+        assert_eq!(
+            chains_from_line("&").unwrap_err().to_string(),
+            "syntax error near unexpected token `&'"
+        );
+    }
+
+    #[test]
+    fn chains_are_displayed_like_the_input() {
+        let line = "a b >out 2>&1 | c <in && d 3<>f || e >&-";
+        assert_eq!(parse_chains(line)[0].to_string(), line);
+    }
+}
@@ -0,0 +1,253 @@
+//! Apply redirections like `> out.txt` or `2>&1`.
+//!
+//! Redirections are processed from left to right, as POSIX requires.
+//! That's why `cmd 2>&1 >file` sends errors to where the output used to go
+//! and only the output to the file, while `cmd >file 2>&1` sends both to the file.
+//!
+//! The shell opens the files itself, so errors are reported before anything runs.
+//! The file descriptors are rearranged in the child process, right before
+//! it executes the new program (see [`CommandExt::pre_exec`]).
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::{AsRawFd, IntoRawFd, OwnedFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+use crate::parser::{Redirect, RedirectKind};
+use crate::sys;
+
+/// The lowest file descriptor the shell uses for its own purposes.
+///
+/// Like in bash, redirecting higher descriptors can conflict with these.
+pub const SHELL_FD_BASE: RawFd = 10;
+
+/// One step of rearranging the file descriptors.
+enum FdAction {
+    /// Make `dst` a copy of `src`.
+    Dup2 { src: RawFd, dst: RawFd },
+    /// Make `dst` refer to an opened file.
+    File { file: OwnedFd, dst: RawFd },
+    /// Close the file descriptor.
+    Close(RawFd),
+}
+
+/// The redirections of a command, with all files already opened.
+pub struct Redirections {
+    actions: Vec<FdAction>,
+}
+
+impl Redirections {
+    /// Open the files of `redirects` and work out how to rearrange the file descriptors.
+    ///
+    /// With `noclobber`, `>` refuses to overwrite existing files.
+    /// The error is a message for the user, like `out.txt: Permission denied`.
+    pub fn open(redirects: &[Redirect], noclobber: bool) -> std::result::Result<Self, String> {
+        let mut actions = vec![];
+        // Whether a file descriptor is open after the actions so far.
+        let mut open: HashMap<RawFd, bool> = HashMap::new();
+
+        for redirect in redirects {
+            let fd = redirect.fd;
+            match redirect.kind {
+                RedirectKind::Duplicate if redirect.target == "-" => {
+                    actions.push(FdAction::Close(fd));
+                    open.insert(fd, false);
+                }
+                RedirectKind::Duplicate => {
+                    let src: RawFd = redirect
+                        .target
+                        .parse()
+                        .map_err(|_| format!("{}: ambiguous redirect", redirect.target))?;
+                    if !open.get(&src).copied().unwrap_or_else(|| sys::is_open(src)) {
+                        return Err(format!("{src}: Bad file descriptor"));
+                    }
+                    actions.push(FdAction::Dup2 { src, dst: fd });
+                    open.insert(fd, true);
+                }
+                _ => {
+                    let file = open_file(redirect, noclobber)
+                        .map_err(|e| format!("{}: {}", redirect.target, error_message(&e)))?;
+                    // Move the file out of the way of the descriptors the user can redirect.
+                    let file = sys::dup_at_least(file.as_raw_fd(), SHELL_FD_BASE)
+                        .map_err(|e| format!("{}: {}", redirect.target, error_message(&e)))?;
+                    actions.push(FdAction::File { file, dst: fd });
+                    open.insert(fd, true);
+                }
+            }
+        }
+
+        Ok(Self { actions })
+    }
+
+    /// Rearrange the file descriptors of the current process.
+    ///
+    /// This only uses async-signal-safe system calls, so it can run between
+    /// `fork` and `exec`.
+    fn apply(&self) -> io::Result<()> {
+        for action in &self.actions {
+            let (src, dst) = match action {
+                FdAction::Dup2 { src, dst } => (*src, *dst),
+                FdAction::File { file, dst } => (file.as_raw_fd(), *dst),
+                FdAction::Close(fd) => {
+                    // Closing a file descriptor that isn't open is fine.
+                    sys::close(*fd).unwrap_or(());
+                    continue;
+                }
+            };
+            if src == dst {
+                // `dup2` does nothing then, but the descriptor must survive `exec`.
+                sys::set_cloexec(dst, false)?;
+            } else {
+                sys::dup2(src, dst)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Connect `fd` to `pipe` before the other redirections are applied.
+    ///
+    /// Like in other shells, `cmd 2>&1 | less` sends errors into the pipe,
+    /// because the pipe is set up first.
+    pub fn with_pipe(mut self, fd: RawFd, pipe: OwnedFd) -> io::Result<Self> {
+        // Move the pipe out of the way of the descriptors the user can redirect.
+        let file = sys::dup_at_least(pipe.as_raw_fd(), SHELL_FD_BASE)?;
+        self.actions.insert(0, FdAction::File { file, dst: fd });
+        Ok(self)
+    }
+
+    /// Apply the redirections to the shell itself, so a builtin can use them.
+    ///
+    /// The original file descriptors are restored when the returned
+    /// [`SavedFds`] is dropped.
+    pub fn apply_in_shell(self) -> io::Result<SavedFds> {
+        let mut saved = SavedFds { fds: vec![] };
+        for action in &self.actions {
+            match action {
+                FdAction::Dup2 { dst, .. } | FdAction::File { dst, .. } => saved.save(*dst)?,
+                FdAction::Close(fd) => saved.save(*fd)?,
+            }
+        }
+        self.apply()?;
+        Ok(saved)
+    }
+
+    /// Apply the redirections to the process that `command` starts.
+    pub fn apply_to(self, command: &mut Command) {
+        // The closure owns the opened files, so they stay open as long as `command`.
+        // SAFETY: `apply` only calls async-signal-safe functions and doesn't allocate.
+        unsafe {
+            command.pre_exec(move || self.apply());
+        }
+    }
+}
+
+/// File descriptors of the shell that were replaced while a builtin runs.
+///
+/// Dropping this puts the original file descriptors back.
+pub struct SavedFds {
+    /// The replaced file descriptors, with a copy of the original,
+    /// or `None` if it wasn't open.
+    fds: Vec<(RawFd, Option<OwnedFd>)>,
+}
+
+impl SavedFds {
+    /// Keep the redirections instead of restoring the original file
+    /// descriptors, like `exec 2>log` does.
+    pub fn keep(mut self) {
+        // Dropping the copies closes them.
+        self.fds.clear();
+    }
+
+    fn save(&mut self, fd: RawFd) -> io::Result<()> {
+        if self.fds.iter().any(|(saved, _)| *saved == fd) {
+            return Ok(());
+        }
+        let copy = if sys::is_open(fd) {
+            Some(sys::dup_at_least(fd, SHELL_FD_BASE)?)
+        } else {
+            None
+        };
+        self.fds.push((fd, copy));
+        Ok(())
+    }
+}
+
+impl Drop for SavedFds {
+    fn drop(&mut self) {
+        for (fd, copy) in self.fds.drain(..).rev() {
+            // There is nothing sensible to do if this fails.
+            let _ = match copy {
+                Some(copy) => sys::dup2(copy.as_raw_fd(), fd),
+                None => sys::close(fd),
+            };
+        }
+    }
+}
+
+/// Open a redirection like `{fd}>file` for the shell itself.
+///
+/// The file gets a new file descriptor, 10 or above, which the shell and
+/// the commands it runs keep until `{fd}>&-` closes it, like in bash.
+pub fn open_persistent(redirect: &Redirect, noclobber: bool) -> Result<RawFd, String> {
+    let fd = match redirect.kind {
+        RedirectKind::Duplicate => {
+            let src: RawFd = redirect
+                .target
+                .parse()
+                .map_err(|_| format!("{}: ambiguous redirect", redirect.target))?;
+            sys::dup_at_least(src, SHELL_FD_BASE)
+                .map_err(|e| format!("{src}: {}", error_message(&e)))?
+        }
+        _ => open_file(redirect, noclobber)
+            .and_then(|file| sys::dup_at_least(file.as_raw_fd(), SHELL_FD_BASE))
+            .map_err(|e| format!("{}: {}", redirect.target, error_message(&e)))?,
+    };
+    sys::set_cloexec(fd.as_raw_fd(), false).map_err(|e| error_message(&e))?;
+    Ok(fd.into_raw_fd())
+}
+
+/// Open the file a redirection like `> out.txt` refers to.
+fn open_file(redirect: &Redirect, noclobber: bool) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    match redirect.kind {
+        RedirectKind::Read => options.read(true),
+        RedirectKind::Write if noclobber => return open_new(&redirect.target),
+        RedirectKind::Write | RedirectKind::Clobber => {
+            options.write(true).create(true).truncate(true)
+        }
+        RedirectKind::Append => options.append(true).create(true),
+        RedirectKind::ReadWrite => options.read(true).write(true).create(true),
+        RedirectKind::Duplicate => unreachable!("duplicating doesn't open a file"),
+    };
+    options.open(&redirect.target)
+}
+
+/// Open `path` for `>` with `noclobber`.
+///
+/// Existing regular files are not overwritten, but writing to
+/// something like `/dev/null` is still allowed.
+fn open_new(path: &str) -> io::Result<File> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            if std::fs::metadata(path)?.is_file() {
+                return Err(io::Error::other("cannot overwrite existing file"));
+            }
+            OpenOptions::new().write(true).open(path)
+        }
+        result => result,
+    }
+}
+
+/// Describe an I/O error the way other shells do.
+///
+/// `io::Error` appends the error code, like in
+/// `Permission denied (os error 13)`, which we don't want to show to users.
+pub fn error_message(e: &io::Error) -> String {
+    let message = e.to_string();
+    match message.find(" (os error ") {
+        Some(end) => message[..end].to_string(),
+        None => message,
+    }
+}
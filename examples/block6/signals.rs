@@ -0,0 +1,234 @@
+//! What the shell does when it receives a signal.
+//!
+//! An interactive shell must not die from Ctrl-C or stop from Ctrl-Z, so it
+//! changes the action of some signals, and the `trap` builtin can change
+//! more. The programs the shell runs must get the default actions back,
+//! so every change is recorded here and undone in child processes
+//! (see [`restore_defaults`]).
+
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use crate::sys;
+
+/// One more than the highest signal number.
+const NSIG: usize = 65;
+
+/// The names of the signals, without the `SIG` prefix, as `trap` and `kill` use them.
+pub const NAMES: &[(&str, libc::c_int)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    ("SYS", libc::SIGSYS),
+];
+
+/// Find the signal called `name`, like `INT`, `SIGINT`, `sigint` or `2`.
+pub fn number(name: &str) -> Option<libc::c_int> {
+    if let Ok(number) = name.parse() {
+        return (1..NSIG as libc::c_int).contains(&number).then_some(number);
+    }
+    let upper = name.to_ascii_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    NAMES
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, number)| *number)
+}
+
+/// The name of `signal`, without the `SIG` prefix.
+pub fn name(signal: libc::c_int) -> Option<&'static str> {
+    NAMES
+        .iter()
+        .find(|(_, number)| *number == signal)
+        .map(|(name, _)| *name)
+}
+
+/// What happens when a signal arrives.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Default = 0,
+    Ignore = 1,
+    /// The signal is recorded for [`take_pending`], and interrupts the system
+    /// call the shell is waiting in.
+    Catch = 2,
+}
+
+impl Action {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Action::Default),
+            1 => Some(Action::Ignore),
+            2 => Some(Action::Catch),
+            _ => None,
+        }
+    }
+}
+
+/// No trap is set for a signal.
+const NO_TRAP: u8 = u8::MAX;
+
+/// The actions the shell uses by itself, like ignoring Ctrl-Z.
+static SHELL_ACTIONS: [AtomicU8; NSIG] = [const { AtomicU8::new(Action::Default as u8) }; NSIG];
+
+/// The actions set by the `trap` builtin, which win over [`SHELL_ACTIONS`].
+static TRAP_ACTIONS: [AtomicU8; NSIG] = [const { AtomicU8::new(NO_TRAP) }; NSIG];
+
+/// The caught signals that arrived since they were last checked.
+static PENDING: [AtomicBool; NSIG] = [const { AtomicBool::new(false) }; NSIG];
+
+/// The handler of caught signals. It only takes note of the signal,
+/// which is one of the few things that are safe in a signal handler.
+extern "C" fn record(signal: libc::c_int) {
+    if let Some(pending) = PENDING.get(signal as usize) {
+        pending.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Apply `action` to `signal`.
+fn apply(signal: libc::c_int, action: Action) {
+    let handler = match action {
+        Action::Default => libc::SIG_DFL,
+        Action::Ignore => libc::SIG_IGN,
+        Action::Catch => record as extern "C" fn(libc::c_int) as libc::sighandler_t,
+    };
+    // `SIGKILL` and `SIGSTOP` can't be changed, which is fine.
+    let _ = sys::sigaction(signal, handler);
+}
+
+/// The action of `signal`, taking traps into account.
+fn current(signal: usize) -> Action {
+    Action::from_u8(TRAP_ACTIONS[signal].load(Ordering::Relaxed))
+        .or_else(|| Action::from_u8(SHELL_ACTIONS[signal].load(Ordering::Relaxed)))
+        .unwrap_or(Action::Default)
+}
+
+/// Set the action the shell uses for `signal` by itself.
+fn set_shell_action(signal: libc::c_int, action: Action) {
+    let Some(shell_action) = SHELL_ACTIONS.get(signal as usize) else {
+        return;
+    };
+    shell_action.store(action as u8, Ordering::Relaxed);
+    apply(signal, current(signal as usize));
+}
+
+/// Make the shell ignore `signal`.
+pub fn ignore(signal: libc::c_int) {
+    set_shell_action(signal, Action::Ignore);
+}
+
+/// Catch `signal`: instead of its default action, it is recorded for
+/// [`take_pending`], and it interrupts the system call the shell is waiting in.
+pub fn catch(signal: libc::c_int) {
+    set_shell_action(signal, Action::Catch);
+}
+
+/// Set the action of `signal` for a trap, or remove the trap with `None`.
+pub fn trap(signal: libc::c_int, action: Option<Action>) {
+    let Some(trap_action) = TRAP_ACTIONS.get(signal as usize) else {
+        return;
+    };
+    trap_action.store(
+        action.map_or(NO_TRAP, |action| action as u8),
+        Ordering::Relaxed,
+    );
+    apply(signal, current(signal as usize));
+}
+
+/// Check whether `signal` arrived and wasn't handled yet.
+pub fn is_pending(signal: libc::c_int) -> bool {
+    PENDING
+        .get(signal as usize)
+        .is_some_and(|pending| pending.load(Ordering::Relaxed))
+}
+
+/// The first caught signal that arrived and wasn't handled yet, if any.
+pub fn any_pending() -> Option<libc::c_int> {
+    (1..NSIG)
+        .find(|&signal| PENDING[signal].load(Ordering::Relaxed))
+        .map(|signal| signal as libc::c_int)
+}
+
+/// Get the caught signals that arrived since the last check.
+pub fn take_pending() -> Vec<libc::c_int> {
+    (1..NSIG)
+        .filter(|&signal| PENDING[signal].swap(false, Ordering::Relaxed))
+        .map(|signal| signal as libc::c_int)
+        .collect()
+}
+
+/// Restore the default action of the signals the shell changed,
+/// in a child process that is going to run a command.
+///
+/// Signals ignored with `trap '' SIG` stay ignored, like in other shells.
+/// Rust ignores `SIGPIPE`, but a command in a pipeline should die from it,
+/// so it is restored too. This runs between `fork` and `exec`, so it only
+/// uses async-signal-safe functions.
+pub fn restore_defaults() {
+    reset_actions();
+    for signal in 1..NSIG {
+        SHELL_ACTIONS[signal].store(Action::Default as u8, Ordering::Relaxed);
+        if TRAP_ACTIONS[signal].load(Ordering::Relaxed) != Action::Ignore as u8 {
+            TRAP_ACTIONS[signal].store(NO_TRAP, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Give the signals the default actions a new program should start with,
+/// like [`restore_defaults`], but remember the actions of the shell.
+///
+/// `exec` uses this before the shell replaces itself with another program,
+/// and [`reapply_actions`] undoes it if that fails.
+pub fn reset_actions() {
+    for signal in 1..NSIG {
+        let trap_action = TRAP_ACTIONS[signal].load(Ordering::Relaxed);
+        let shell_action = SHELL_ACTIONS[signal].load(Ordering::Relaxed);
+        if trap_action == Action::Ignore as u8 {
+            continue;
+        }
+        if trap_action != NO_TRAP
+            || shell_action != Action::Default as u8
+            || signal == libc::SIGPIPE as usize
+        {
+            apply(signal as libc::c_int, Action::Default);
+        }
+    }
+}
+
+/// Apply the actions of the shell again, after [`reset_actions`].
+pub fn reapply_actions() {
+    for signal in 1..NSIG {
+        match current(signal) {
+            // Like Rust does at startup, so writing to a closed pipe is an error
+            // instead of killing the shell.
+            Action::Default if signal == libc::SIGPIPE as usize => {
+                apply(libc::SIGPIPE, Action::Ignore)
+            }
+            Action::Default => {}
+            action => apply(signal as libc::c_int, action),
+        }
+    }
+}
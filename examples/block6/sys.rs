@@ -0,0 +1,231 @@
+//! Thin wrappers around system calls that `std` doesn't expose.
+//!
+//! The C functions report errors by returning `-1` and setting `errno`.
+//! The wrappers turn that into an `io::Error`, so we can use `?` on them.
+
+use std::io;
+use std::os::fd::{FromRawFd, OwnedFd, RawFd};
+
+/// Turn the `-1` error convention of libc into an `io::Result`.
+fn cvt(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+/// Duplicate `fd` to the lowest free file descriptor that is at least `min`.
+///
+/// The new descriptor is closed automatically when a program is executed.
+pub fn dup_at_least(fd: RawFd, min: RawFd) -> io::Result<OwnedFd> {
+    // SAFETY: `fcntl` doesn't touch memory, and on success we own the new descriptor.
+    unsafe {
+        let new = cvt(libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, min))?;
+        Ok(OwnedFd::from_raw_fd(new))
+    }
+}
+
+/// Make `dst` refer to the same file as `src`, closing `dst` first if needed.
+pub fn dup2(src: RawFd, dst: RawFd) -> io::Result<()> {
+    // SAFETY: `dup2` doesn't touch memory.
+    cvt(unsafe { libc::dup2(src, dst) })?;
+    Ok(())
+}
+
+/// Close the file descriptor `fd`, which we don't own as an `OwnedFd`.
+pub fn close(fd: RawFd) -> io::Result<()> {
+    // SAFETY: `close` doesn't touch memory. The callers make sure nothing else owns `fd`.
+    cvt(unsafe { libc::close(fd) })?;
+    Ok(())
+}
+
+/// Check whether `fd` is an open file descriptor.
+pub fn is_open(fd: RawFd) -> bool {
+    // SAFETY: `F_GETFD` only reads the descriptor flags.
+    unsafe { libc::fcntl(fd, libc::F_GETFD) != -1 }
+}
+
+/// Set or clear the close-on-exec flag of `fd`.
+pub fn set_cloexec(fd: RawFd, cloexec: bool) -> io::Result<()> {
+    let flags = if cloexec { libc::FD_CLOEXEC } else { 0 };
+    // SAFETY: `F_SETFD` only changes the descriptor flags.
+    cvt(unsafe { libc::fcntl(fd, libc::F_SETFD, flags) })?;
+    Ok(())
+}
+
+/// Read from the file descriptor `fd` into `buf`, returning how many bytes were read.
+pub fn read(fd: RawFd, buf: &mut [u8]) -> io::Result<usize> {
+    // SAFETY: `buf` is valid for writes of `buf.len()` bytes.
+    let read = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+    if read == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(read as usize)
+    }
+}
+
+/// Write `buf` to the file descriptor `fd`, returning how many bytes were written.
+pub fn write(fd: RawFd, buf: &[u8]) -> io::Result<usize> {
+    // SAFETY: `buf` is valid for reads of `buf.len()` bytes.
+    let written = unsafe { libc::write(fd, buf.as_ptr().cast(), buf.len()) };
+    if written == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(written as usize)
+    }
+}
+
+/// Create a child process that is a copy of the shell.
+///
+/// Returns `0` in the child, and the pid of the child in the shell.
+///
+/// # Safety
+///
+/// Only the calling thread is copied into the child. If other threads exist,
+/// locks they hold (like the one of the allocator) stay locked forever.
+pub unsafe fn fork() -> io::Result<libc::pid_t> {
+    cvt(libc::fork())
+}
+
+/// Wait for the child process `pid` to change state, and return its raw wait
+/// status with the resources it used if it ended.
+///
+/// With `WNOHANG` in `flags`, returns `None` if the child is still running.
+pub fn wait4(
+    pid: libc::pid_t,
+    flags: libc::c_int,
+) -> io::Result<Option<(libc::c_int, libc::rusage)>> {
+    let mut status = 0;
+    // SAFETY: `rusage` is a plain C struct, for which zeroes are valid.
+    let mut usage = unsafe { std::mem::zeroed() };
+    loop {
+        // SAFETY: `status` and `usage` are valid for writes.
+        match cvt(unsafe { libc::wait4(pid, &mut status, flags, &mut usage) }) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some((status, usage))),
+        }
+    }
+}
+
+/// Wait for any child process to change state, and return its pid, its raw
+/// wait status and the resources it used if it ended.
+///
+/// Unlike [`wait4`], this fails with `Interrupted` when a caught signal arrives.
+pub fn wait_any(flags: libc::c_int) -> io::Result<(libc::pid_t, libc::c_int, libc::rusage)> {
+    let mut status = 0;
+    // SAFETY: `rusage` is a plain C struct, for which zeroes are valid.
+    let mut usage = unsafe { std::mem::zeroed() };
+    // SAFETY: `status` and `usage` are valid for writes.
+    let pid = cvt(unsafe { libc::wait4(-1, &mut status, flags, &mut usage) })?;
+    Ok((pid, status, usage))
+}
+
+/// Move the process `pid` into the process group `pgid`.
+///
+/// A `pid` of `0` means the calling process, and a `pgid` of `0` makes
+/// a new group whose id is the pid of the process.
+pub fn setpgid(pid: libc::pid_t, pgid: libc::pid_t) -> io::Result<()> {
+    // SAFETY: `setpgid` doesn't touch memory.
+    cvt(unsafe { libc::setpgid(pid, pgid) })?;
+    Ok(())
+}
+
+/// Get the process group of the calling process.
+pub fn getpgrp() -> libc::pid_t {
+    // SAFETY: `getpgrp` always succeeds.
+    unsafe { libc::getpgrp() }
+}
+
+/// Make `pgid` the foreground process group of the terminal `fd`.
+pub fn tcsetpgrp(fd: RawFd, pgid: libc::pid_t) -> io::Result<()> {
+    // SAFETY: `tcsetpgrp` doesn't touch memory.
+    cvt(unsafe { libc::tcsetpgrp(fd, pgid) })?;
+    Ok(())
+}
+
+/// Send `signal` to the process `pid`, or to the process group `-pid`.
+pub fn kill(pid: libc::pid_t, signal: libc::c_int) -> io::Result<()> {
+    // SAFETY: `kill` doesn't touch memory.
+    cvt(unsafe { libc::kill(pid, signal) })?;
+    Ok(())
+}
+
+/// Get the modes of the terminal `fd`, like whether it echoes what is typed.
+pub fn tcgetattr(fd: RawFd) -> io::Result<libc::termios> {
+    let mut modes = std::mem::MaybeUninit::uninit();
+    // SAFETY: `modes` is valid for writes, and initialized when `tcgetattr` succeeds.
+    unsafe {
+        cvt(libc::tcgetattr(fd, modes.as_mut_ptr()))?;
+        Ok(modes.assume_init())
+    }
+}
+
+/// Set the modes of the terminal `fd`, once the output written so far is sent.
+pub fn tcsetattr(fd: RawFd, modes: &libc::termios) -> io::Result<()> {
+    // SAFETY: `modes` is valid for reads.
+    cvt(unsafe { libc::tcsetattr(fd, libc::TCSADRAIN, modes) })?;
+    Ok(())
+}
+
+/// Describe `signal` for users, like "Terminated" for `SIGTERM`.
+pub fn signal_description(signal: libc::c_int) -> String {
+    // SAFETY: `strsignal` returns a valid C string, which we copy right away.
+    // The shell has no other threads that could call it at the same time.
+    unsafe {
+        let description = libc::strsignal(signal);
+        if description.is_null() {
+            return format!("Signal {signal}");
+        }
+        std::ffi::CStr::from_ptr(description)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Set what happens when `signal` arrives: `SIG_DFL`, `SIG_IGN` or a handler function.
+///
+/// System calls that a handler interrupts fail with `EINTR` instead of restarting,
+/// so the shell can notice the signal while it waits for input.
+pub fn sigaction(signal: libc::c_int, handler: libc::sighandler_t) -> io::Result<()> {
+    // SAFETY: an all-zero `sigaction` is valid, with an empty mask and no flags.
+    // The callers only pass handlers that are async-signal-safe.
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler;
+        cvt(libc::sigaction(signal, &action, std::ptr::null_mut()))?;
+    }
+    Ok(())
+}
+
+/// Get the soft and hard limits of the shell on `resource`, like `RLIMIT_NOFILE`.
+pub fn getrlimit(resource: libc::c_int) -> io::Result<libc::rlimit> {
+    let mut limit = std::mem::MaybeUninit::uninit();
+    // SAFETY: `limit` is valid for writes, and initialized when `getrlimit` succeeds.
+    unsafe {
+        cvt(libc::getrlimit(resource as _, limit.as_mut_ptr()))?;
+        Ok(limit.assume_init())
+    }
+}
+
+/// Set the limits of the shell on `resource`. The programs it starts inherit them.
+pub fn setrlimit(resource: libc::c_int, limit: &libc::rlimit) -> io::Result<()> {
+    // SAFETY: `limit` is valid for reads.
+    cvt(unsafe { libc::setrlimit(resource as _, limit) })?;
+    Ok(())
+}
+
+/// Set the file mode creation mask of the shell, and return the previous one.
+pub fn umask(mask: libc::mode_t) -> libc::mode_t {
+    // SAFETY: `umask` is always safe to call, and can't fail.
+    unsafe { libc::umask(mask) }
+}
+
+/// End the current process right away, without running destructors
+/// or flushing buffers that belong to the shell.
+pub fn exit_now(code: i32) -> ! {
+    // SAFETY: `_exit` is always safe to call.
+    unsafe { libc::_exit(code) }
+}
@@ -0,0 +1,118 @@
+//! Report how long a pipeline took, for the `time` keyword.
+//!
+//! The real time is measured by the shell. The CPU time comes from the
+//! kernel, which tells `wait4` what each child used once it has ended, so
+//! the time of all the commands of a pipeline is added up.
+
+use std::time::Duration;
+
+/// The format `time` uses, unless `TIMEFORMAT` is set.
+pub const DEFAULT_FORMAT: &str = "\nreal\t%3lR\nuser\t%3lU\nsys\t%3lS";
+/// The format of `time -p`, which POSIX defines.
+pub const POSIX_FORMAT: &str = "real %2R\nuser %2U\nsys %2S";
+/// The line `time -v` adds, with the most memory a command used.
+pub const VERBOSE_FORMAT: &str = "\nmaxrss\t%MKB";
+
+/// The resources that the processes of a job used.
+#[derive(Clone, Copy, Default)]
+pub struct Usage {
+    pub user: Duration,
+    pub system: Duration,
+    /// The largest resident set size of the processes, in kilobytes.
+    pub max_rss: i64,
+}
+
+impl Usage {
+    /// Add what a process that ended used.
+    pub fn add(&mut self, usage: &libc::rusage) {
+        let duration =
+            |time: libc::timeval| Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000);
+        self.user += duration(usage.ru_utime);
+        self.system += duration(usage.ru_stime);
+        self.max_rss = self.max_rss.max(usage.ru_maxrss);
+    }
+}
+
+/// Write the times like bash does with `TIMEFORMAT`.
+///
+/// `%R`, `%U` and `%S` are the real, user and system time in seconds, and
+/// `%P` is the share of the real time spent on the CPU. A digit sets how many
+/// decimals to show (3 by default), and `l` shows the minutes apart, like
+/// `%3lR`. `%M` is the maximum resident set size, and `%%` is a `%`.
+pub fn format(format: &str, real: Duration, usage: &Usage) -> String {
+    let mut output = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        let precision = chars
+            .next_if(char::is_ascii_digit)
+            .and_then(|digit| digit.to_digit(10))
+            .map_or(3, |digit| digit.min(3) as usize);
+        let long = chars.next_if_eq(&'l').is_some();
+        let time = match chars.next() {
+            Some('R') => real,
+            Some('U') => usage.user,
+            Some('S') => usage.system,
+            Some('P') => {
+                let cpu = (usage.user + usage.system).as_secs_f64();
+                let share = match real.is_zero() {
+                    true => 0.0,
+                    false => cpu * 100.0 / real.as_secs_f64(),
+                };
+                output.push_str(&format!("{share:.2}"));
+                continue;
+            }
+            Some('M') => {
+                output.push_str(&usage.max_rss.to_string());
+                continue;
+            }
+            Some('%') => {
+                output.push('%');
+                continue;
+            }
+            // Like in bash, an unknown conversion is left as it is.
+            c => {
+                output.push('%');
+                output.extend(c);
+                continue;
+            }
+        };
+        let seconds = time.as_secs_f64();
+        match long {
+            true => output.push_str(&format!(
+                "{}m{:.precision$}s",
+                time.as_secs() / 60,
+                seconds % 60.0
+            )),
+            false => output.push_str(&format!("{seconds:.precision$}")),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_are_formatted() {
+        let usage = Usage {
+            user: Duration::from_millis(1500),
+            system: Duration::from_millis(250),
+            max_rss: 2048,
+        };
+        let real = Duration::from_millis(63_500);
+        assert_eq!(
+            format(DEFAULT_FORMAT, real, &usage),
+            "\nreal\t1m3.500s\nuser\t0m1.500s\nsys\t0m0.250s"
+        );
+        assert_eq!(
+            format(POSIX_FORMAT, real, &usage),
+            "real 63.50\nuser 1.50\nsys 0.25"
+        );
+        assert_eq!(format("%0R %M%% %x", real, &usage), "64 2048% %x");
+    }
+}
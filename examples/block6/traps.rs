@@ -0,0 +1,140 @@
+//! The commands set with the `trap` builtin.
+//!
+//! A trap runs a command when a signal arrives, or on one of the events
+//! other shells call pseudo-signals: when the shell exits (`EXIT`), after a
+//! command fails (`ERR`), before each command (`DEBUG`), or when a file run
+//! with `source` is done (`RETURN`).
+//!
+//! Signals arrive at any time, but running a command from a signal handler
+//! isn't safe. The handler only takes note of the signal, and the shell runs
+//! the trap at the next safe point, between two commands.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::signals::{self, Action};
+
+/// Something a trap can be set for.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Event {
+    Exit,
+    Signal(libc::c_int),
+    Debug,
+    Err,
+    Return,
+}
+
+impl Event {
+    /// Parse a signal spec of `trap`, like `EXIT`, `INT`, `SIGINT` or `2`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        match spec.to_ascii_uppercase().as_str() {
+            "EXIT" | "0" => Some(Event::Exit),
+            "DEBUG" => Some(Event::Debug),
+            "ERR" => Some(Event::Err),
+            "RETURN" => Some(Event::Return),
+            _ => signals::number(spec).map(Event::Signal),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Exit => write!(f, "EXIT"),
+            Event::Signal(signal) => match signals::name(*signal) {
+                Some(name) => write!(f, "SIG{name}"),
+                None => write!(f, "{signal}"),
+            },
+            Event::Debug => write!(f, "DEBUG"),
+            Event::Err => write!(f, "ERR"),
+            Event::Return => write!(f, "RETURN"),
+        }
+    }
+}
+
+/// The traps of the shell.
+#[derive(Default)]
+pub struct Traps {
+    /// The command of each trap. An empty command ignores the signal.
+    commands: BTreeMap<Event, String>,
+}
+
+impl Traps {
+    /// The command of the trap for `event`, if it does something.
+    pub fn get(&self, event: Event) -> Option<&str> {
+        self.commands
+            .get(&event)
+            .map(String::as_str)
+            .filter(|command| !command.is_empty())
+    }
+
+    /// Set the trap for `event` to `command`, or remove it with `None`.
+    pub fn set(&mut self, event: Event, command: Option<String>) {
+        if let Event::Signal(signal) = event {
+            let action = command.as_ref().map(|command| match command.is_empty() {
+                true => Action::Ignore,
+                false => Action::Catch,
+            });
+            signals::trap(signal, action);
+        }
+        match command {
+            Some(command) => self.commands.insert(event, command),
+            None => self.commands.remove(&event),
+        };
+    }
+
+    /// Forget the traps, in a copy of the shell that runs in the background.
+    ///
+    /// Like in other shells, ignored signals stay ignored.
+    pub fn enter_subshell(&mut self) {
+        self.commands
+            .retain(|event, command| matches!(event, Event::Signal(_)) && command.is_empty());
+    }
+
+    /// Write the traps for `events`, or all of them, as `trap` commands
+    /// that set them again.
+    pub fn list(&self, events: &[Event], out: &mut impl Write) -> io::Result<()> {
+        for (event, command) in &self.commands {
+            if events.is_empty() || events.contains(event) {
+                writeln!(out, "trap -- {} {event}", quote(command))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Quote `word` with single quotes, so the shell reads it back as it is.
+fn quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signal_specs_are_parsed() {
+        assert_eq!(Event::parse("EXIT"), Some(Event::Exit));
+        assert_eq!(Event::parse("0"), Some(Event::Exit));
+        assert_eq!(Event::parse("INT"), Some(Event::Signal(libc::SIGINT)));
+        assert_eq!(Event::parse("sigterm"), Some(Event::Signal(libc::SIGTERM)));
+        assert_eq!(Event::parse("9"), Some(Event::Signal(libc::SIGKILL)));
+        assert_eq!(Event::parse("err"), Some(Event::Err));
+        assert_eq!(Event::parse("NOPE"), None);
+        assert_eq!(Event::parse("999"), None);
+    }
+
+    #[test]
+    fn traps_are_listed_as_commands() {
+        let mut traps = Traps::default();
+        traps.set(Event::Debug, Some("echo 'hi'".to_string()));
+        traps.set(Event::Exit, Some("rm -f tmp".to_string()));
+        let mut out = Vec::new();
+        traps.list(&[], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "trap -- 'rm -f tmp' EXIT\ntrap -- 'echo '\\''hi'\\''' DEBUG\n"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! The variables of the shell.
+//!
+//! Most variables are environment variables, which the programs the shell
+//! runs inherit. The environment can only hold strings, though, so arrays
+//! like `PIPESTATUS` only live in the shell.
+
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct Variables {
+    arrays: HashMap<String, Vec<String>>,
+}
+
+impl Variables {
+    /// Get the value of the variable `name`, which can have an index like
+    /// `NAME[1]`. `NAME[@]` or `NAME[*]` are all the elements, separated
+    /// with spaces. Without an index, an array gives its first element.
+    pub fn get(&self, name: &str) -> Option<String> {
+        let (name, index) = match name.strip_suffix(']').and_then(|name| name.split_once('[')) {
+            Some((name, index)) => (name, index),
+            None => (name, "0"),
+        };
+        let values = match self.arrays.get(name) {
+            Some(values) => values.clone(),
+            // A string is like an array with a single element.
+            None => vec![std::env::var(name).ok()?],
+        };
+        match index {
+            "@" | "*" => Some(values.join(" ")),
+            index => values.get(index.parse::<usize>().ok()?).cloned(),
+        }
+    }
+
+    /// Set the variable `name` to `value`, in the shell only. With an index,
+    /// like `NAME[1]`, only that element of the array is set.
+    pub fn set(&mut self, name: &str, value: String) {
+        let Some((name, index)) = name.strip_suffix(']').and_then(|name| name.split_once('['))
+        else {
+            return self.set_array(name, vec![value]);
+        };
+        let Ok(index) = index.parse::<usize>() else {
+            return;
+        };
+        let values = self.arrays.entry(name.to_string()).or_insert_with(|| {
+            // A string becomes the first element of the array.
+            std::env::var(name).into_iter().collect()
+        });
+        if values.len() <= index {
+            values.resize(index + 1, String::new());
+        }
+        values[index] = value;
+    }
+
+    /// Set the array `name` to `values`.
+    pub fn set_array(&mut self, name: &str, values: Vec<String>) {
+        self.arrays.insert(name.to_string(), values);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrays_are_indexed() {
+        let mut variables = Variables::default();
+        variables.set_array("A", vec!["0".to_string(), "1".to_string()]);
+        assert_eq!(variables.get("A").as_deref(), Some("0"));
+        assert_eq!(variables.get("A[1]").as_deref(), Some("1"));
+        assert_eq!(variables.get("A[@]").as_deref(), Some("0 1"));
+        assert_eq!(variables.get("A[2]"), None);
+        assert_eq!(variables.get("A[x]"), None);
+        variables.set("A[3]", "3".to_string());
+        assert_eq!(variables.get("A[@]").as_deref(), Some("0 1  3"));
+    }
+}
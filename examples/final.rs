@@ -1,5 +1,6 @@
 use std::{
     io,
+    io::BufRead,
     io::IsTerminal,
     io::Write,
     iter::Peekable,
@@ -41,7 +42,7 @@ impl<'a> Chain<'a> {
         }
     }
 
-    fn run(self) {
+    fn run(self) -> Option<Output> {
         let mut prev_output: Option<Output> = None;
         for e in self.elements {
             match e {
@@ -49,14 +50,14 @@ impl<'a> Chain<'a> {
                     prev_output = cmd.run();
                 }
                 Element::And => {
-                    let status = prev_output.expect("no command before &&").status;
+                    let status = prev_output.as_ref().expect("no command before &&").status;
                     if !status.success() {
                         break;
                     }
                     prev_output = None;
                 }
                 Element::Or => {
-                    let status = prev_output.expect("no command before ||").status;
+                    let status = prev_output.as_ref().expect("no command before ||").status;
                     if status.success() {
                         break;
                     }
@@ -64,6 +65,7 @@ impl<'a> Chain<'a> {
                 }
             }
         }
+        prev_output
     }
 }
 
@@ -125,14 +127,22 @@ impl<'a> Cmd<'a> {
 }
 
 fn main() {
+    let mut status = 0;
     loop {
         show_prompt();
-        let line = read_line();
+        // End of input (Ctrl-D or a closed pipe).
+        let Some(line) = read_line() else {
+            break;
+        };
         let chains = chains_from_line(&line);
         for chain in chains {
-            chain.run();
+            if let Some(output) = chain.run() {
+                status = output.status.code().unwrap_or(1);
+            }
         }
     }
+    // Like other shells, exit with the status of the last command.
+    std::process::exit(status);
 }
 
 /// If stdout is printed to a terminal, print a prompt.
@@ -147,15 +157,23 @@ fn show_prompt() {
     }
 }
 
-fn read_line() -> String {
-    let mut line = String::new();
-    io::stdin()
-        .read_line(&mut line)
-        .expect("failed to read line from stdin");
-    line
+/// Read a line from stdin, or `None` once the input ended.
+///
+/// Bytes that aren't valid UTF-8 are replaced with the replacement character,
+/// so a binary file piped into the shell doesn't crash it.
+fn read_line() -> Option<String> {
+    let mut line = Vec::new();
+    match io::stdin().lock().read_until(b'\n', &mut line) {
+        Ok(0) => None,
+        Ok(_) => Some(String::from_utf8_lossy(&line).into_owned()),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
 }
 
-fn chains_from_line(line: &str) -> impl Iterator<Item = Chain> {
+fn chains_from_line(line: &str) -> impl Iterator<Item = Chain<'_>> {
     // For simplicity sake, this workshop uses the split function.
     // This is inefficient because it parses the whole line.
     // If you feel adventurous, try to parse the line character by character instead. 🤠
@@ -1,4 +1,4 @@
-use std::io::{self, IsTerminal, Write};
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::process::Command;
 
 static PROMPT: &[u8] = ">> ".as_bytes();
@@ -11,12 +11,12 @@ struct Cmd {
 }
 
 impl Cmd {
-    fn from_string(text: &str) -> Self {
+    fn from_string(text: &str) -> Option<Self> {
         let mut chunks = text.split_whitespace();
-        Self {
-            program: chunks.next().unwrap().to_string(),
+        Some(Self {
+            program: chunks.next()?.to_string(),
             args: chunks.map(|s| s.to_string()).collect(),
-        }
+        })
     }
 
     pub fn run(&self) -> io::Result<()> {
@@ -35,28 +35,35 @@ impl Cmd {
 fn show_prompt() -> io::Result<()> {
     let mut output = io::stdout();
     if output.is_terminal() {
-        output.write(PROMPT)?;
+        output.write_all(PROMPT)?;
         output.flush()?;
     }
     Ok(())
 }
 
-fn read_stdin() -> io::Result<String> {
-    let mut buf = String::new();
-    let stdin = io::stdin();
-    stdin.read_line(&mut buf)?;
-    Ok(buf)
+/// Read a line from stdin, or `None` at the end of the input.
+/// Invalid UTF-8 is replaced instead of failing.
+fn read_stdin() -> io::Result<Option<String>> {
+    let mut buf = Vec::new();
+    if io::stdin().lock().read_until(b'\n', &mut buf)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&buf).into_owned()))
 }
 
 fn main() -> io::Result<()> {
     loop {
         show_prompt()?;
-        let line = read_stdin()?;
+        // End of input (Ctrl-D or a closed pipe).
+        let Some(line) = read_stdin()? else {
+            break;
+        };
         if line.trim() == EXIT {
             break;
         }
-        let cmd = Cmd::from_string(&line);
-        cmd.run()?;
+        if let Some(cmd) = Cmd::from_string(&line) {
+            cmd.run()?;
+        }
     }
     Ok(())
 }
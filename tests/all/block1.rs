@@ -1,13 +1,8 @@
-use std::time::Duration;
-
 use crate::utils::ShellRunner;
 
 #[test]
 fn shell_runs_pwd() {
-    let output = ShellRunner::new()
-        .with_stdin("pwd")
-        .kill_after(Duration::from_secs(1))
-        .run();
+    let output = ShellRunner::new().with_stdin("pwd").run();
 
     let stdout_str = String::from_utf8(output.stdout).unwrap();
     let curr_dir_path = std::env::current_dir().unwrap();
@@ -1,15 +1,10 @@
-use std::time::Duration;
-
 use crate::utils::ShellRunner;
 
-const SHELL_TIMEOUT: Duration = Duration::from_secs(3);
-
 #[test]
 fn shell_runs_pwd_twice() {
     let output = ShellRunner::new()
         .with_stdin("pwd; pwd")
         .example("block2")
-        .kill_after(SHELL_TIMEOUT)
         .run();
 
     let stdout_str = String::from_utf8(output.stdout).unwrap();
@@ -23,7 +18,6 @@ fn shell_understands_and_operator() {
     let output = ShellRunner::new()
         .with_stdin("echo hello && echo world")
         .example("block2")
-        .kill_after(SHELL_TIMEOUT)
         .run();
 
     let stdout_str = String::from_utf8(output.stdout).unwrap();
@@ -35,9 +29,18 @@ fn shell_understands_or_operator() {
     let output = ShellRunner::new()
         .with_stdin("echo hello || echo world")
         .example("block2")
-        .kill_after(SHELL_TIMEOUT)
         .run();
 
     let stdout_str = String::from_utf8(output.stdout).unwrap();
     assert_eq!(stdout_str, "hello\n");
 }
+
+#[test]
+fn shell_exits_with_the_last_status_at_end_of_input() {
+    let output = ShellRunner::new()
+        .with_stdin("true; false")
+        .example("block2")
+        .run();
+
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -1,15 +1,10 @@
-use std::time::Duration;
-
 use crate::utils::ShellRunner;
 
-const SHELL_TIMEOUT: Duration = Duration::from_secs(3);
-
 #[test]
 fn supports_cd_shell_builtin() {
     let output = ShellRunner::new()
         .with_stdin("pwd; cd examples; pwd")
         .example("block3")
-        .kill_after(SHELL_TIMEOUT)
         .run();
 
     let stdout_str = String::from_utf8(output.stdout).unwrap();
@@ -23,7 +18,6 @@ fn supports_relative_cd() {
     let output = ShellRunner::new()
         .with_stdin("pwd; cd examples; pwd; cd ..; pwd")
         .example("block3")
-        .kill_after(SHELL_TIMEOUT)
         .run();
 
     let stdout_str = String::from_utf8(output.stdout).unwrap();
@@ -40,7 +34,6 @@ fn invalid_cd_doesnt_change_dir() {
     let output = ShellRunner::new()
         .with_stdin("pwd; cd invalid_dir; pwd")
         .example("block3")
-        .kill_after(SHELL_TIMEOUT)
         .run();
 
     let stdout_str = String::from_utf8(output.stdout).unwrap();
@@ -54,7 +47,6 @@ fn supports_exit_builtin() {
     let output = ShellRunner::new()
         .with_stdin("exit 1")
         .example("block3")
-        .kill_after(SHELL_TIMEOUT)
         .run();
 
     assert_eq!(output.status.code(), Some(1));
@@ -1,20 +1,6 @@
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::{env, fs, path::PathBuf, process, time::Duration};
+use std::fs;
 
-use crate::utils::ShellRunner;
-
-const SHELL_TIMEOUT: Duration = Duration::from_secs(3);
-
-fn generate_temp_file_name() -> PathBuf {
-    let temp_dir = env::temp_dir();
-    let pid = process::id();
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_nanos();
-    let file_name = format!("temp_file_{}_{}", pid, timestamp);
-    temp_dir.join(file_name)
-}
+use crate::utils::{generate_temp_file_name, ShellRunner};
 
 #[test]
 fn test_history() {
@@ -25,9 +11,6 @@ fn test_history() {
     ShellRunner::new()
         .with_stdin("echo 1\necho 2\nhistory")
         .example("block4")
-        // This test takes longer to complete sometimes so we increase the
-        // timeout
-        .kill_after(SHELL_TIMEOUT + Duration::from_secs(5))
         .run();
 
     let history_contents = fs::read_to_string(history_path).unwrap();
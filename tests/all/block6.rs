@@ -0,0 +1,650 @@
+use std::process::Output;
+
+use crate::utils::{ShellRunner, TempPath};
+
+/// Run `stdin` in the block 6 shell, with a fresh history file.
+fn run_shell(stdin: &str) -> String {
+    let output = run_shell_with(stdin, &[]);
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Run `stdin` in the block 6 shell with more environment variables, and
+/// get everything it did. The history file is fresh and removed afterwards,
+/// unless `envs` sets `HISTORY_PATH`.
+fn run_shell_with(stdin: &str, envs: &[(&str, String)]) -> Output {
+    let history = TempPath::new();
+    let mut runner = ShellRunner::new()
+        .with_stdin(stdin)
+        .example("block6")
+        .env("HISTORY_PATH", history.display().to_string());
+    // Later variables replace earlier ones.
+    for (key, value) in envs {
+        runner = runner.env(key, value.clone());
+    }
+    runner.run()
+}
+
+#[test]
+fn history_expansion_repeats_commands() {
+    let stdout = run_shell("set -H -o history\necho one two\n!!\necho !$\n^two^three\n");
+    assert_eq!(
+        stdout,
+        "one two\necho one two\none two\necho two\ntwo\necho three\nthree\n"
+    );
+}
+
+#[test]
+fn history_is_recorded_expanded() {
+    let history_path = TempPath::new();
+    let envs = [("HISTORY_PATH", history_path.display().to_string())];
+    // Like in bash, scripts only record history with `set -o history`.
+    run_shell_with("echo a.txt\n", &envs);
+    assert!(!history_path.exists());
+
+    run_shell_with("set -H -o history\necho a.txt\necho !$:r.bak\n", &envs);
+    let history = std::fs::read_to_string(&history_path).unwrap();
+    assert_eq!(history, "echo a.txt\necho a.bak\n");
+}
+
+#[test]
+fn history_expansion_can_be_turned_off() {
+    // Like in bash, it is only on by default in interactive shells.
+    let stdout =
+        run_shell("echo \"hi!x\"\nset -H\necho one\nset +H\necho !!\nset -o | grep histexpand\n");
+    assert_eq!(stdout, "hi!x\none\n!!\nhistexpand     \toff\n");
+}
+
+#[test]
+fn output_is_redirected_to_files() {
+    let out = TempPath::new();
+    let out = out.display();
+    let stdout = run_shell(&format!(
+        "echo one > {out}\n> {out} echo two\necho three >>{out}\ncat < {out}\nwc -l <{out} | cat\n"
+    ));
+    assert_eq!(stdout, "two\nthree\n2\n");
+}
+
+#[test]
+fn redirection_errors_are_reported() {
+    let output = run_shell_with("echo hi > / || echo failed\ncat < /nonexistent\n", &[]);
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "failed\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: /: Is a directory\nrush: /nonexistent: No such file or directory\n"
+    );
+}
+
+#[test]
+fn redirections_are_processed_in_order() {
+    let out = TempPath::new();
+    let out = out.display();
+    let print_both = "sh -c 'echo out; echo err >&2'";
+    let stdout = run_shell(&format!(
+        "{print_both} 2>&1 >{out}\ncat {out}\n{print_both} >{out} 2>&1\ncat {out}\n"
+    ));
+    assert_eq!(stdout, "err\nout\nout\nerr\n");
+}
+
+#[test]
+fn stderr_can_be_redirected_and_merged() {
+    let out = TempPath::new();
+    let out = out.display();
+    let print_both = "sh -c 'echo out; echo err >&2'";
+    let output = run_shell_with(
+        &format!(
+            "{print_both} 2>/dev/null\n{print_both} &>{out}\n{print_both} &>>{out}\n\
+             {print_both} 2>>{out} >&2\ncat {out}\n"
+        ),
+        &[],
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "out\nout\nerr\nout\nerr\nout\nerr\n"
+    );
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+}
+
+#[test]
+fn other_file_descriptors_can_be_redirected() {
+    let out = TempPath::new();
+    let out = out.display();
+    let stdout = run_shell(&format!(
+        "sh -c 'echo three >&3' 3>{out}\nsh -c 'echo nine >&9' 9<>{out}\ncat {out}\n\
+         cat <&- 2>/dev/null || echo closed\n"
+    ));
+    // `<>` doesn't truncate, so the end of `three` is still there.
+    assert_eq!(stdout, "nine\n\nclosed\n");
+}
+
+#[test]
+fn duplicating_a_closed_descriptor_fails() {
+    let output = run_shell_with("echo hi >&8 || echo failed\n", &[]);
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "failed\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: 8: Bad file descriptor\n"
+    );
+}
+
+#[test]
+fn noclobber_protects_existing_files() {
+    let out = TempPath::new();
+    let rc = TempPath::new();
+    std::fs::write(
+        &rc,
+        "# Don't overwrite files by accident\nset -o noclobber\n",
+    )
+    .unwrap();
+
+    let output = run_shell_with(
+        &format!(
+            "echo one > {out}\necho two > {out} || cat {out}\necho three >| {out}\n\
+             cat {out}\nset +C\necho four > {out}\ncat {out}\necho five > /dev/null\n",
+            out = out.display()
+        ),
+        &[("RUSHRC", rc.display().to_string())],
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "one\nthree\nfour\n"
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("rush: {}: cannot overwrite existing file\n", out.display())
+    );
+}
+
+#[test]
+fn builtin_output_can_be_redirected() {
+    let out = TempPath::new();
+    let output = run_shell_with(
+        &format!(
+            "set -o history\necho hi\nhistory > {out}\ncat {out}\ncd /nonexistent 2>/dev/null || echo failed\n\
+             set -o >&2\nhistory | grep -c echo\n",
+            out = out.display()
+        ),
+        &[],
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("hi\necho hi\nhistory > {}\nfailed\n3\n", out.display())
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "errexit        \toff\nhistexpand     \toff\nhistory        \ton\nmonitor        \toff\nnoclobber      \toff\n\
+         noexec         \toff\nnoglob         \toff\nnounset        \toff\n\
+         pipefail       \toff\nverbose        \toff\nxtrace         \toff\n"
+    );
+}
+
+#[test]
+fn pipelines_stream_between_commands() {
+    // `yes` never ends: this only works if `head` reads while `yes` writes.
+    let stdout =
+        run_shell("set -o history\nyes | head -n 3\nseq 200000 | tail -n 1\nhistory | head -n 1\n");
+    assert_eq!(stdout, "y\ny\ny\n200000\nyes | head -n 3\n");
+}
+
+#[test]
+fn and_or_skip_only_the_next_pipeline() {
+    let stdout = run_shell(
+        "false && echo a || echo b\ntrue || echo c && echo d\ncd / | cat; pwd | grep -cx /\n",
+    );
+    assert_eq!(stdout, "b\nd\n0\n");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn commands_write_to_the_output_of_the_shell() {
+    // Without passthrough, each command would get a pipe of its own.
+    let stdout = run_shell("readlink /proc/self/fd/1\nreadlink /proc/self/fd/1\n");
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], lines[1]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn jobs_run_in_the_background() {
+    // The first job runs until the marker exists, which is only created
+    // once the shell waits for the job in `fg`, so no timing is involved.
+    let marker = TempPath::new();
+    let marker = marker.display();
+    let job = format!("sh -c until [ -e {marker} ]; do sleep 0.01; done");
+    let stdout = run_shell(&format!(
+        "set -m\nsh -c 'until [ -e {marker} ]; do sleep 0.01; done' &\necho hi | cat &\n\
+         wait %2\njobs\n\
+         sh -c 'until grep -q do_wait /proc/$PPID/wchan; do sleep 0.01; done; touch {marker}' &\n\
+         fg %1\nwait\njobs\n"
+    ));
+    assert_eq!(
+        stdout,
+        format!("hi\n[1]+  Running                 {job} &\n{job}\n")
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn jobs_of_a_script_stay_in_its_process_group() {
+    // The fifth field of /proc/PID/stat is the process group.
+    let group = "sh -c 'cut -d\" \" -f5 /proc/$$/stat /proc/$PPID/stat' &\nwait\n";
+    let stdout = run_shell(&format!("{group}set -m\n{group}"));
+    let groups: Vec<_> = stdout.lines().collect();
+    assert_eq!(groups.len(), 4);
+    assert_eq!(groups[0], groups[1]);
+    assert_ne!(groups[2], groups[3]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn jobs_of_a_script_are_reaped_when_they_end() {
+    // The job is a zombie, with state Z in /proc/PID/stat, until the shell
+    // reaps it before reading the next line.
+    let stdout = run_shell(
+        "sh -c 'exit 3' &\n\
+         sh -c 'until grep -q \"^[0-9]* (sh) Z\" /proc/$1/stat; do sleep 0.01; done' - $!\n\
+         test -e /proc/$!\necho $?\nwait $!\necho $?\n",
+    );
+    assert_eq!(stdout, "1\n3\n");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn stopped_jobs_can_be_continued() {
+    // The resumed job writes its pid to the marker, and `jobs` only runs
+    // once the job is a zombie, with state Z in /proc/PID/stat.
+    let marker = TempPath::new();
+    let marker = marker.display();
+    let job = format!("sh -c kill -TSTP $$; echo resumed; echo $$ >{marker}");
+    let stdout = run_shell(&format!(
+        "set -m\nsh -c 'kill -TSTP $$; echo resumed; echo $$ >{marker}'\njobs\nbg %sh\n\
+         sh -c 'until [ -s {marker} ] && grep -q \" Z \" /proc/$(cat {marker})/stat; do sleep 0.01; done'\n\
+         jobs\nsh -c 'kill -STOP $$; echo again'\nfg\n",
+    ));
+    assert_eq!(
+        stdout,
+        format!(
+            "[1]+  Stopped                 {job}\n\
+             [1]+ {job} &\n\
+             resumed\n\
+             [1]+  Done                    {job}\n\
+             sh -c kill -STOP $$; echo again\n\
+             again\n"
+        )
+    );
+}
+
+#[test]
+fn commands_killed_by_signals_have_status_128_plus_the_signal() {
+    let output = run_shell_with("sh -c 'kill -TERM $$'\nexit\n", &[]);
+    // SIGTERM is 15.
+    assert_eq!(output.status.code(), Some(143));
+}
+
+#[test]
+fn traps_run_on_signals_and_exit() {
+    let output = run_shell_with(
+        "trap 'echo cleanup' EXIT\ntrap 'echo caught' USR1\ntrap -p USR1\n\
+             sh -c 'kill -USR1 $PPID'; echo after\nexit 3\necho not reached\n",
+        &[],
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "trap -- 'echo caught' SIGUSR1\ncaught\nafter\ncleanup\n"
+    );
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn pseudo_signal_traps_run() {
+    let script = TempPath::new();
+    std::fs::write(&script, "echo sourced\n").unwrap();
+    let stdout = run_shell(&format!(
+        "trap 'echo error' ERR\nfalse\nfalse || true\ntrap - ERR\nfalse\n\
+         trap 'echo debug' DEBUG\necho command\ntrap - DEBUG\n\
+         trap 'echo returned' RETURN\nsource {}\n",
+        script.display()
+    ));
+    assert_eq!(stdout, "error\ndebug\ncommand\ndebug\nsourced\nreturned\n");
+}
+
+#[test]
+fn commands_that_cannot_run_are_reported() {
+    let run = |stdin| run_shell_with(stdin, &[]);
+
+    let output = run("rush-no-such-command\n./rush-no-such-file\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: rush-no-such-command: command not found\n\
+         rush: ./rush-no-such-file: No such file or directory\n"
+    );
+    assert_eq!(output.status.code(), Some(127));
+
+    let output = run("/\nsh -c 'kill -TERM $$' || echo killed\n/\n");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "killed\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: /: Is a directory\nTerminated\nrush: /: Is a directory\n"
+    );
+    assert_eq!(output.status.code(), Some(126));
+}
+
+#[test]
+fn parameters_and_patterns_are_expanded() {
+    let dir = TempPath::new();
+    std::fs::create_dir(&dir).unwrap();
+    for file in ["a.txt", "b.txt", ".hidden.txt", "c.rs"] {
+        std::fs::write(dir.join(file), "").unwrap();
+    }
+    let stdout = run_shell(&format!(
+        "cd {}\necho *.txt '*.txt' \\*.rs\nfalse; echo $? \"$?\" '$?'\n\
+         set -f; echo *.txt; set +f\nshopt -s dotglob nullglob; echo *.txt none*\n",
+        dir.display()
+    ));
+    assert_eq!(
+        stdout,
+        "a.txt b.txt *.txt *.rs\n1 1 $?\n*.txt\n.hidden.txt a.txt b.txt\n"
+    );
+}
+
+#[test]
+fn set_options_change_how_scripts_run() {
+    let output = run_shell_with(
+        "echo $-\nset -euo pipefail\necho $-\nset -o | grep pipefail\n\
+             set -x; echo 'a b'; set +x\nfalse || echo handled\nfalse\necho not reached\n",
+        &[("PS4", "> ".to_string())],
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\neu\npipefail       \ton\na b\nhandled\n"
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "> echo 'a b'\n> set +x\n"
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run_shell_with("set -u\necho $UNSET_VARIABLE\necho not reached\n", &[]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: UNSET_VARIABLE: unbound variable\n"
+    );
+}
+
+#[test]
+fn the_status_of_every_command_of_a_pipeline_is_recorded() {
+    let stdout = run_shell(
+        "true | false | sh -c 'exit 3'\necho ${PIPESTATUS[@]} $PIPESTATUS ${PIPESTATUS[1]}\n\
+         false | true; echo $?\nset -o pipefail\nsh -c 'exit 4' | false | true; echo $?\n\
+         true | true; echo $?\n",
+    );
+    assert_eq!(stdout, "0 1 3 0 1\n0\n1\n0\n");
+}
+
+#[test]
+fn exec_changes_the_file_descriptors_of_the_shell() {
+    let out = TempPath::new();
+    let log = TempPath::new();
+    let stdout = run_shell(&format!(
+        "exec 3>{out}\necho three >&3\nexec {{fd}}>>{out}\necho var >&$fd\nexec {{fd}}>&-\n\
+         exec 2>{log}\nrush-no-such-command\necho still here\n",
+        out = out.display(),
+        log = log.display()
+    ));
+    let written = std::fs::read_to_string(&out).unwrap();
+    let logged = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(stdout, "still here\n");
+    assert_eq!(written, "three\nvar\n");
+    assert_eq!(logged, "rush: rush-no-such-command: command not found\n");
+
+    // When the program can't start, the redirections are undone.
+    let output = run_shell_with(
+        &format!(
+            "exec -x 3>{out}\necho three >&3\ntrap 'echo bye' EXIT\n\
+             exec rush-no-such-command >{out}\n",
+            out = out.display()
+        ),
+        &[],
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "bye\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: exec: -x: invalid option\nrush: 3: Bad file descriptor\n\
+         rush: exec: rush-no-such-command: command not found\n"
+    );
+    assert_eq!(output.status.code(), Some(127));
+}
+
+#[test]
+fn exec_replaces_the_shell() {
+    let output = run_shell_with(
+        "exec -a name sh -c 'echo $0; exit 4'\necho not reached\n",
+        &[],
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "name\n");
+    assert_eq!(output.status.code(), Some(4));
+
+    let stdout = run_shell("exec -l sh -c 'echo $0'\n");
+    assert_eq!(stdout, "-sh\n");
+}
+
+#[test]
+fn programs_are_remembered_until_they_are_gone() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempPath::new();
+    std::fs::create_dir(&dir).unwrap();
+    let program = dir.join("rush-program");
+    std::fs::write(&program, "#!/bin/sh\necho ran\n").unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = run_shell_with(
+        &format!(
+            "hash\nrush-program; rush-program\nhash\nrm {program}\nrush-program\nhash\n\
+             hash -r; hash -p /bin/echo say; say hi\nhash\n",
+            program = program.display()
+        ),
+        &[(
+            "PATH",
+            format!("{}:/bin:{}", dir.display(), std::env::var("PATH").unwrap()),
+        )],
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "hash: hash table empty\nran\nran\nhits\tcommand\n   2\t{}\n\
+             hits\tcommand\n   1\t/bin/rm\nhi\nhits\tcommand\n   1\t/bin/echo\n",
+            program.display()
+        )
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: rush-program: command not found\n"
+    );
+}
+
+#[test]
+fn type_tells_what_a_name_runs() {
+    let stdout = run_shell(
+        "type cd /bin/sh\ntype -t exit /bin/sh nope\necho $?\ncommand -v cd /bin/sh\n\
+         command echo hi\nbuiltin cd /\npwd\nbuiltin echo no\necho $?\n",
+    );
+    assert_eq!(
+        stdout,
+        "cd is a shell builtin\n/bin/sh is /bin/sh\nbuiltin\nfile\n1\ncd\n/bin/sh\n\
+         hi\n/\n1\n"
+    );
+}
+
+#[test]
+fn time_reports_how_long_a_pipeline_took() {
+    let output = run_shell_with(
+        "time sleep 0.2 | cat\ntime -p false\necho $?\n",
+        &[("TIMEFORMAT", "took %1R".to_string())],
+    );
+
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let (first, rest) = stderr.split_once('\n').unwrap();
+    // A busy machine can make the pipeline slower, but never faster.
+    let took: f64 = first.strip_prefix("took ").unwrap().parse().unwrap();
+    assert!(took >= 0.2, "{stderr}");
+    assert!(
+        rest.starts_with("real ") && rest.contains("\nuser "),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn ulimit_and_umask_change_what_programs_inherit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let file = TempPath::new();
+    let stdout = run_shell(&format!(
+        "ulimit -n 64\nulimit -n\nsh -c 'ulimit -n'\nulimit -Sf 10\nulimit -f\n\
+         ulimit -a 100\necho $?\nulimit -f\n\
+         umask 027\numask\numask -S\numask g+w,o=r\numask\ntouch {}\n",
+        file.display()
+    ));
+    let mode = std::fs::metadata(&file).unwrap().permissions().mode();
+
+    assert_eq!(stdout, "64\n64\n10\n2\n10\n0027\nu=rwx,g=rx,o=\n0003\n");
+    assert_eq!(mode & 0o777, 0o664);
+}
+
+#[test]
+fn wait_returns_the_status_of_background_jobs() {
+    // The first job ends only once the marker exists, so `wait -n` can
+    // only see the second one.
+    let marker = TempPath::new();
+    let marker = marker.display();
+    let stdout = run_shell(&format!(
+        "sh -c 'sleep 0.2; exit 3' &\nsh -c 'exit 4' &\nwait $!\necho $?\nwait\necho $?\n\
+         sh -c 'until [ -e {marker} ]; do sleep 0.01; done; exit 5' &\nsh -c 'exit 6' &\n\
+         wait -n\necho $?\ntouch {marker}\nwait %1\necho $?\nwait -n\necho $?\n"
+    ));
+    assert_eq!(stdout, "4\n0\n6\n5\n127\n");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn wait_is_interrupted_by_trapped_signals() {
+    // The signal is only sent once the shell is blocked in `wait`.
+    let stdout = run_shell(
+        "trap 'echo trapped' USR1\n\
+         sh -c 'until grep -q do_wait /proc/$PPID/wchan; do sleep 0.01; done; kill -USR1 $PPID' &\n\
+         sleep 5 &\nwait $!\necho $?\nkill $!\n",
+    );
+    assert_eq!(stdout, "trapped\n138\n");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn kill_sends_signals_to_jobs_and_processes() {
+    // `jobs` only runs once the job has stopped, with state T in /proc/PID/stat.
+    let output = run_shell_with(
+        "sleep 10 &\nkill %1\nwait %1\necho $?\nsleep 10 &\nkill -s KILL $!\nwait $!\necho $?\n\
+             sleep 10 &\nkill -STOP %sleep\n\
+             sh -c 'until grep -q \" T \" /proc/$1/stat; do sleep 0.01; done' - $!\n\
+             jobs\nkill -n 2 %1\nwait %1\necho $?\n\
+             kill -l 130 TERM\nkill -l | head -1\nkill -BOGUS %1\nkill nope\n",
+        &[],
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "143\n137\n[1]+  Stopped (signal)        sleep 10\n130\nINT\n15\n 1) SIGHUP\t 2) SIGINT\t 3) SIGQUIT\t 4) SIGILL\t 5) SIGTRAP\n"
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: kill: BOGUS: invalid signal specification\n\
+         rush: kill: nope: arguments must be process or job IDs\n"
+    );
+}
+
+#[test]
+fn disowned_jobs_are_forgotten() {
+    let stdout = run_shell(
+        "sleep 10 &\nsleep 10 &\ndisown -h %1\ndisown\njobs\ndisown %2\necho $?\n\
+         kill %1\ndisown -a\njobs\nkill $!\nshopt -s huponexit\nshopt -p huponexit\n",
+    );
+    assert_eq!(
+        stdout,
+        "[1]+  Running                 sleep 10 &\n1\nshopt -s huponexit\n"
+    );
+}
+
+#[test]
+fn coprocesses_talk_to_the_shell_through_pipes() {
+    let stdout = run_shell(
+        "coproc sh -c 'while read line; do echo \"got $line\"; done'\n\
+         echo hello >&${COPROC[1]}\nhead -n 1 <&${COPROC[0]}\n\
+         coproc UPPER sh -c 'read line; echo $line | tr a-z A-Z'\n\
+         echo shout >&${UPPER[1]}\nhead -n 1 <&${UPPER[0]}\n\
+         wait $UPPER_PID\necho $?\ntype -t coproc\n",
+    );
+    assert_eq!(stdout, "got hello\nSHOUT\n0\nkeyword\n");
+}
+
+#[test]
+fn coprocesses_see_the_end_of_their_input() {
+    let stdout = run_shell(
+        "coproc cat\ncoproc OTHER cat\necho hi >&${COPROC[1]}\nexec {COPROC[1]}>&-\n\
+         wait $COPROC_PID\necho $?\ncat <&${COPROC[0]}\nexec {OTHER[1]}>&-\nwait\n",
+    );
+    assert_eq!(stdout, "0\nhi\n");
+}
+
+#[test]
+fn cd_keeps_pwd_and_oldpwd_up_to_date() {
+    let root = TempPath::new();
+    std::fs::create_dir_all(root.join("real/sub")).unwrap();
+    std::fs::create_dir_all(root.join("projects/rush")).unwrap();
+    std::os::unix::fs::symlink(root.join("real/sub"), root.join("link")).unwrap();
+    let root = root.display();
+    let output = run_shell_with(
+        &format!(
+            "cd {root}/link\necho $PWD\ncd ..\necho $PWD $OLDPWD\ncd -P link\necho $PWD\n\
+             cd -\ncd\necho $PWD\ncd rush\ncd a b\n"
+        ),
+        &[
+            ("HOME", format!("{root}/real")),
+            ("CDPATH", format!(":{root}/projects")),
+        ],
+    );
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!(
+            "{root}/link\n{root} {root}/link\n{root}/real/sub\n{root}\n\
+             {root}/real\n{root}/projects/rush\n"
+        )
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: cd: too many arguments\n"
+    );
+}
+
+#[test]
+fn exit_needs_a_numeric_status() {
+    let output = run_shell_with(
+        "exit 1 2\necho still here\nexit abc\necho not reached\n",
+        &[],
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "still here\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "rush: exit: too many arguments\nrush: exit: abc: numeric argument required\n"
+    );
+    assert_eq!(output.status.code(), Some(2));
+}
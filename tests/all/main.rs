@@ -3,5 +3,6 @@ mod block2;
 mod block3;
 mod block4;
 mod block5;
+mod block6;
 
 mod utils;
@@ -1,22 +1,68 @@
 use std::{
+    env, fs,
     io::Write,
-    process::{Child, Command, Output, Stdio},
+    ops::Deref,
+    path::{Path, PathBuf},
+    process::{self, Child, Command, Output, Stdio},
     thread,
-    time::Duration,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
+pub fn generate_temp_file_name() -> PathBuf {
+    let temp_dir = env::temp_dir();
+    let pid = process::id();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_nanos();
+    let file_name = format!("temp_file_{}_{}", pid, timestamp);
+    temp_dir.join(file_name)
+}
+
+/// A temporary file or directory, removed when it goes out of scope,
+/// even if the test fails.
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    pub fn new() -> Self {
+        Self(generate_temp_file_name())
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        // Only one of them applies, and the path may not have been created.
+        let _ = fs::remove_dir_all(&self.0);
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
 pub struct ShellRunner<'a> {
     stdin: Option<&'a str>,
-    kill_after: Option<Duration>,
     example: Option<&'a str>,
+    envs: Vec<(&'a str, String)>,
 }
 
 impl<'a> ShellRunner<'a> {
     pub fn new() -> Self {
         Self {
             stdin: None,
-            kill_after: None,
             example: None,
+            envs: Vec::new(),
         }
     }
 
@@ -30,10 +76,10 @@ impl<'a> ShellRunner<'a> {
         self
     }
 
-    /// Wait duration and kill the command afterwards.
-    /// Useful to test commands that don't exit on their own.
-    pub fn kill_after(mut self, duration: Duration) -> Self {
-        self.kill_after = Some(duration);
+    /// Set an environment variable for the shell only,
+    /// without changing the environment of the test process.
+    pub fn env(mut self, key: &'a str, value: impl Into<String>) -> Self {
+        self.envs.push((key, value.into()));
         self
     }
 
@@ -45,11 +91,15 @@ impl<'a> ShellRunner<'a> {
 
     fn run_shell(&self) -> Child {
         let mut command = Command::new("cargo");
-        command.arg("run");
+        command.args(["run", "--quiet"]);
         if let Some(example) = self.example {
             command.args(["--example", example]);
         }
-        command.stdin(Stdio::piped()).stdout(Stdio::piped());
+        command.envs(self.envs.iter().map(|(key, value)| (key, value)));
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
 
         command.spawn().unwrap()
     }
@@ -67,12 +117,7 @@ impl<'a> ShellRunner<'a> {
         }
     }
 
-    fn wait(&self, mut child: Child) -> Output {
-        if let Some(duration) = self.kill_after {
-            std::thread::sleep(duration);
-            child.kill().unwrap();
-        }
-
+    fn wait(&self, child: Child) -> Output {
         child.wait_with_output().unwrap()
     }
 }